@@ -0,0 +1,85 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use movingai::{parser::parse_map_file, Coords2D, Map2D, MovingAiMap};
+use std::collections::VecDeque;
+use std::path::Path;
+
+/// Expand every tile reachable from `start` in breadth-first order.
+fn expand_all(map: &MovingAiMap, start: Coords2D) -> usize {
+    let mut visited = vec![false; map.width() * map.height()];
+    let mut queue = VecDeque::new();
+    visited[start.1 * map.width() + start.0] = true;
+    queue.push_back(start);
+    let mut expanded = 0;
+    while let Some(current) = queue.pop_front() {
+        expanded += 1;
+        for neighbor in map.neighbors(current) {
+            let index = neighbor.1 * map.width() + neighbor.0;
+            if !visited[index] {
+                visited[index] = true;
+                queue.push_back(neighbor);
+            }
+        }
+    }
+    expanded
+}
+
+/// Build a 1024x1024 map tiling the 512x512 maze four times.
+///
+/// The maze is walled on its top and left sides, so the copies would be
+/// disconnected from each other: the seams between them are opened to make a
+/// single connected map.
+fn tiled_map(map: &MovingAiMap) -> MovingAiMap {
+    let (width, height) = (map.width() * 2, map.height() * 2);
+    let tiles = (0..height)
+        .flat_map(|y| (0..width).map(move |x| (x, y)))
+        .map(|(x, y)| {
+            let seam = x == map.width() || y == map.height();
+            match *map.get((x % map.width(), y % map.height())) {
+                '@' if seam => '.',
+                tile => tile,
+            }
+        })
+        .collect();
+    MovingAiMap::new(String::from("octile"), height, width, tiles).unwrap()
+}
+
+fn expansion_benchmark(c: &mut Criterion) {
+    let map = parse_map_file(Path::new("tests/maze512-32-9.map")).expect("Failed to load test map");
+    let large = tiled_map(&map);
+    let start = map
+        .coords()
+        .find(|&coord| map.is_traversable(coord))
+        .expect("The test map has no free tile");
+    assert_eq!(
+        expand_all(&large, start),
+        large.free_states(),
+        "Every free tile of the 1024x1024 map must be reachable"
+    );
+
+    c.bench_function("expansion_512x512", |b| {
+        b.iter(|| black_box(expand_all(&map, black_box(start))));
+    });
+
+    c.bench_function("expansion_1024x1024", |b| {
+        b.iter(|| black_box(expand_all(&large, black_box(start))));
+    });
+
+    c.bench_function("get_all_tiles_1024x1024", |b| {
+        b.iter(|| {
+            for coord in large.coords() {
+                black_box(*large.get(coord));
+            }
+        });
+    });
+
+    c.bench_function("get_copied_all_tiles_1024x1024", |b| {
+        b.iter(|| {
+            for coord in large.coords() {
+                black_box(large.get_copied(coord));
+            }
+        });
+    });
+}
+
+criterion_group!(benches, expansion_benchmark);
+criterion_main!(benches);
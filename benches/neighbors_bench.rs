@@ -1,5 +1,5 @@
 use criterion::{black_box, criterion_group, criterion_main, Criterion};
-use movingai::{parser::parse_map_file, Map2D};
+use movingai::{parser::parse_map_file, Map2D, MovingAiMap};
 use std::path::Path;
 
 fn neighbors_benchmark(c: &mut Criterion) {
@@ -31,6 +31,21 @@ fn neighbors_benchmark(c: &mut Criterion) {
             });
         });
     }
+
+    // The same maze as a 16-connected map: knight moves are only paid for by
+    // the callers of `knight_neighbors`.
+    let tiles = map.coords().map(|coord| map.get_copied(coord)).collect();
+    let (height, width) = (map.height(), map.width());
+    let sixteen =
+        MovingAiMap::new(String::from("sixteen-connected"), height, width, tiles).unwrap();
+    c.bench_function("sixteen_connected_all_traversable", |b| {
+        b.iter(|| {
+            for &coord in &traversable_coords {
+                black_box(sixteen.neighbors(black_box(coord)));
+                black_box(sixteen.knight_neighbors(black_box(coord)));
+            }
+        });
+    });
 }
 
 criterion_group!(benches, neighbors_benchmark);
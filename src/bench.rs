@@ -0,0 +1,351 @@
+use std::collections::BTreeMap;
+use std::io::{self, Write};
+use std::time::{Duration, Instant};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::map2d::{Coords2D, MovingAiMap, SceneRecord};
+use crate::search::SearchResult;
+
+/// The default tolerance when comparing a cost with the optimal length.
+pub const DEFAULT_EPSILON: f64 = 0.0001;
+
+/// Options for running a benchmark.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BenchmarkOptions {
+    /// The maximum difference between the cost found by a solver and the
+    /// optimal length of a record for the query to be correct. Defaults to
+    /// `DEFAULT_EPSILON`.
+    pub epsilon: f64,
+}
+
+impl Default for BenchmarkOptions {
+    fn default() -> BenchmarkOptions {
+        BenchmarkOptions {
+            epsilon: DEFAULT_EPSILON,
+        }
+    }
+}
+
+/// What a solver returns for a query.
+///
+/// Solvers can return the cost of the path (`Option<f64>`) or a whole
+/// `Option<SearchResult>`, which also reports the number of expanded nodes.
+pub trait Solution {
+    /// The cost of the path found, `None` if there is no path.
+    fn cost(&self) -> Option<f64>;
+
+    /// The number of nodes expanded to answer the query, if known.
+    fn expanded(&self) -> Option<usize> {
+        None
+    }
+}
+
+impl Solution for Option<f64> {
+    fn cost(&self) -> Option<f64> {
+        *self
+    }
+}
+
+impl Solution for Option<SearchResult> {
+    fn cost(&self) -> Option<f64> {
+        self.as_ref().map(|result| result.cost)
+    }
+
+    fn expanded(&self) -> Option<usize> {
+        self.as_ref().map(|result| result.expanded)
+    }
+}
+
+/// The outcome of a single query of a benchmark.
+///
+/// With the `serde` feature, `time` is serialized as seconds.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct QueryResult {
+    /// The position of the record in the scenario.
+    pub index: usize,
+    /// The bucket of the record.
+    pub bucket: u32,
+    /// The start of the query.
+    pub start: Coords2D,
+    /// The goal of the query.
+    pub goal: Coords2D,
+    /// The optimal length stored in the record.
+    pub expected: f64,
+    /// The cost found by the solver, `None` if it did not find a path.
+    pub found: Option<f64>,
+    /// The number of nodes expanded by the solver, if it reports them.
+    pub expanded: Option<usize>,
+    /// Whether the solver found a path with the optimal length.
+    pub correct: bool,
+    /// The time taken by the solver.
+    #[cfg_attr(feature = "serde", serde(with = "seconds"))]
+    pub time: Duration,
+}
+
+/// Summary statistics over the times of a set of queries.
+///
+/// Percentiles use the nearest-rank method. All the times are zero if there
+/// are no queries.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct TimeStats {
+    /// The sum of all the times.
+    pub total: Duration,
+    /// The mean time.
+    pub mean: Duration,
+    /// The median time.
+    pub median: Duration,
+    /// The 99th percentile.
+    pub p99: Duration,
+    /// The longest time.
+    pub max: Duration,
+}
+
+impl TimeStats {
+    /// Compute the statistics of `times`.
+    pub fn new(times: &[Duration]) -> TimeStats {
+        if times.is_empty() {
+            return TimeStats::default();
+        }
+        let mut sorted = times.to_vec();
+        sorted.sort_unstable();
+        let total: Duration = sorted.iter().sum();
+        let percentile = |p: f64| {
+            let rank = (p * sorted.len() as f64).ceil() as usize;
+            sorted[rank.clamp(1, sorted.len()) - 1]
+        };
+        TimeStats {
+            total,
+            mean: total / sorted.len() as u32,
+            median: percentile(0.5),
+            p99: percentile(0.99),
+            max: sorted[sorted.len() - 1],
+        }
+    }
+}
+
+/// The statistics of the queries in a bucket.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BucketStats {
+    /// The bucket.
+    pub bucket: u32,
+    /// The number of queries in the bucket.
+    pub queries: usize,
+    /// The number of queries not solved optimally.
+    pub failures: usize,
+    /// The times of the queries in the bucket.
+    pub time: TimeStats,
+}
+
+/// The results of running a solver on a scenario.
+///
+/// The results of the queries can be exported as CSV (see `write_csv`) and,
+/// with the `serde` feature, serialized (e.g., as JSON).
+///
+/// # Examples
+///
+/// ```
+/// use std::path::Path;
+/// use movingai::bench::run_scenarios;
+/// use movingai::parser::{parse_map_file, parse_scen_file};
+/// use movingai::search::astar;
+///
+/// let map = parse_map_file(Path::new("./tests/arena.map")).unwrap();
+/// let scen = parse_scen_file(Path::new("./tests/arena.map.scen")).unwrap();
+/// let report = run_scenarios(&map, &scen[..2], astar);
+/// let csv = report.to_csv();
+/// let mut lines = csv.lines();
+/// assert_eq!(
+///     lines.next(),
+///     Some("index,bucket,start_x,start_y,goal_x,goal_y,expected,found,expanded,correct,time")
+/// );
+/// assert!(lines.next().unwrap().starts_with("0,0,1,11,1,12,1,1,"));
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct BenchmarkReport {
+    /// The tolerance used to check the costs.
+    pub epsilon: f64,
+    /// The result of every query, in the order of the records.
+    pub queries: Vec<QueryResult>,
+}
+
+impl BenchmarkReport {
+    /// The queries that have not been solved optimally.
+    pub fn failures(&self) -> impl Iterator<Item = &QueryResult> {
+        self.queries.iter().filter(|query| !query.correct)
+    }
+
+    /// Check if all the queries have been solved optimally.
+    pub fn is_success(&self) -> bool {
+        self.failures().next().is_none()
+    }
+
+    /// The statistics of the times of all the queries.
+    pub fn time_stats(&self) -> TimeStats {
+        let times: Vec<Duration> = self.queries.iter().map(|query| query.time).collect();
+        TimeStats::new(&times)
+    }
+
+    /// Write the results of the queries as CSV.
+    ///
+    /// There is a header line and a line for every query with the columns
+    /// `index`, `bucket`, `start_x`, `start_y`, `goal_x`, `goal_y`,
+    /// `expected`, `found`, `expanded`, `correct` and `time` (in seconds).
+    /// `found` and `expanded` are empty if unknown.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if it is not possible to write on `writer`.
+    pub fn write_csv<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        writeln!(
+            writer,
+            "index,bucket,start_x,start_y,goal_x,goal_y,expected,found,expanded,correct,time"
+        )?;
+        for query in &self.queries {
+            let found = query.found.map(|cost| cost.to_string()).unwrap_or_default();
+            let expanded = query.expanded.map(|n| n.to_string()).unwrap_or_default();
+            writeln!(
+                writer,
+                "{},{},{},{},{},{},{},{},{},{},{}",
+                query.index,
+                query.bucket,
+                query.start.0,
+                query.start.1,
+                query.goal.0,
+                query.goal.1,
+                query.expected,
+                found,
+                expanded,
+                query.correct,
+                query.time.as_secs_f64()
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Same as `write_csv`, returning a `String`.
+    pub fn to_csv(&self) -> String {
+        let mut out = Vec::new();
+        self.write_csv(&mut out)
+            .expect("writing to a Vec<u8> cannot fail");
+        String::from_utf8(out).expect("the CSV is valid UTF-8")
+    }
+
+    /// The statistics of every bucket, sorted by bucket.
+    pub fn buckets(&self) -> Vec<BucketStats> {
+        let mut buckets: BTreeMap<u32, Vec<&QueryResult>> = BTreeMap::new();
+        for query in &self.queries {
+            buckets.entry(query.bucket).or_default().push(query);
+        }
+        buckets
+            .into_iter()
+            .map(|(bucket, queries)| {
+                let times: Vec<Duration> = queries.iter().map(|query| query.time).collect();
+                BucketStats {
+                    bucket,
+                    queries: queries.len(),
+                    failures: queries.iter().filter(|query| !query.correct).count(),
+                    time: TimeStats::new(&times),
+                }
+            })
+            .collect()
+    }
+}
+
+/// Run `solver` on every record of a scenario and check the costs it finds
+/// against the optimal lengths.
+///
+/// The solver can return the cost of the path or the `SearchResult` of the
+/// crate searches (see `Solution`).
+///
+/// # Arguments
+///  * `map` the map of the scenario.
+///  * `records` the records of the scenario.
+///  * `solver` returns a shortest path between two coordinates of the map
+///    (or just its cost), or `None` if there is no path.
+///
+/// # Examples
+///
+/// ```
+/// use std::path::Path;
+/// use movingai::bench::run_scenarios;
+/// use movingai::parser::{parse_map_file, parse_scen_file};
+/// use movingai::search::astar;
+///
+/// let map = parse_map_file(Path::new("./tests/arena.map")).unwrap();
+/// let scen = parse_scen_file(Path::new("./tests/arena.map.scen")).unwrap();
+/// let report = run_scenarios(&map, &scen, astar);
+/// assert!(report.is_success());
+/// println!("median time: {:?}", report.time_stats().median);
+/// ```
+pub fn run_scenarios<R, F, S>(
+    map: &MovingAiMap<R>,
+    records: &[SceneRecord],
+    solver: F,
+) -> BenchmarkReport
+where
+    F: FnMut(&MovingAiMap<R>, Coords2D, Coords2D) -> S,
+    S: Solution,
+{
+    run_scenarios_with_options(map, records, &BenchmarkOptions::default(), solver)
+}
+
+/// Same as `run_scenarios`, with the given options.
+pub fn run_scenarios_with_options<R, F, S>(
+    map: &MovingAiMap<R>,
+    records: &[SceneRecord],
+    options: &BenchmarkOptions,
+    mut solver: F,
+) -> BenchmarkReport
+where
+    F: FnMut(&MovingAiMap<R>, Coords2D, Coords2D) -> S,
+    S: Solution,
+{
+    let queries = records
+        .iter()
+        .enumerate()
+        .map(|(index, record)| {
+            let begin = Instant::now();
+            let solution = solver(map, record.start_pos, record.goal_pos);
+            let time = begin.elapsed();
+            let found = solution.cost();
+            let correct =
+                found.is_some_and(|cost| (cost - record.optimal_length).abs() <= options.epsilon);
+            QueryResult {
+                index,
+                bucket: record.bucket,
+                start: record.start_pos,
+                goal: record.goal_pos,
+                expected: record.optimal_length,
+                found,
+                expanded: solution.expanded(),
+                correct,
+                time,
+            }
+        })
+        .collect();
+    BenchmarkReport {
+        epsilon: options.epsilon,
+        queries,
+    }
+}
+
+/// Serialize durations as seconds.
+#[cfg(feature = "serde")]
+mod seconds {
+    use std::time::Duration;
+
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(time: &Duration, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_f64(time.as_secs_f64())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Duration, D::Error> {
+        let secs = f64::deserialize(deserializer)?;
+        Duration::try_from_secs_f64(secs).map_err(serde::de::Error::custom)
+    }
+}
@@ -0,0 +1,218 @@
+use crate::map2d::{Coords2D, Map2D};
+
+/// The label of the tiles that do not belong to any component.
+const NONE: u32 = u32::MAX;
+
+/// The position of the neighbors of a Tarjan frame not visited yet.
+const UNVISITED: usize = usize::MAX;
+
+/// The strongly connected components of a map.
+///
+/// Two traversable tiles are in the same component if each of them can be
+/// reached from the other one. Since moves can be one-way (e.g., an agent can
+/// leave water but cannot enter it from regular terrain), a tile can reach
+/// tiles of other components too: `can_reach` answers that.
+///
+/// # Examples
+///
+/// ```
+/// use movingai::components::label_components;
+/// use movingai::MovingAiMap;
+///
+/// // Water can be left but not entered from `.`.
+/// let map = MovingAiMap::new(String::from("octile"), 1, 4, vec!['.', '.', 'W', 'W']).unwrap();
+/// let components = label_components(&map);
+/// assert_eq!(components.count(), 2);
+/// assert_eq!(components.component((0, 0)), components.component((1, 0)));
+/// assert!(components.can_reach((3, 0), (0, 0)));
+/// assert!(!components.can_reach((0, 0), (3, 0)));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Components {
+    width: usize,
+    height: usize,
+    labels: Vec<u32>,
+    sizes: Vec<usize>,
+    /// The components reachable with one step from every component. A
+    /// component only leads to components with a smaller id.
+    successors: Vec<Vec<u32>>,
+}
+
+impl Components {
+    /// The number of components.
+    pub fn count(&self) -> usize {
+        self.sizes.len()
+    }
+
+    /// The number of tiles in every component, indexed by component.
+    pub fn sizes(&self) -> &[usize] {
+        &self.sizes
+    }
+
+    /// The component of the tile at `coords`, `None` if the tile cannot be
+    /// traversed or it is out of bound.
+    pub fn component(&self, coords: Coords2D) -> Option<usize> {
+        if coords.0 >= self.width || coords.1 >= self.height {
+            return None;
+        }
+        match self.labels[coords.1 * self.width + coords.0] {
+            NONE => None,
+            label => Some(label as usize),
+        }
+    }
+
+    /// Check if `a` and `b` are in the same component.
+    pub fn same_component(&self, a: Coords2D, b: Coords2D) -> bool {
+        self.component(a).is_some() && self.component(a) == self.component(b)
+    }
+
+    /// Check if there is a path from `from` to `to`.
+    ///
+    /// It is immediate for tiles in the same component; otherwise it visits
+    /// the graph of the components.
+    pub fn can_reach(&self, from: Coords2D, to: Coords2D) -> bool {
+        let (Some(from), Some(to)) = (self.component(from), self.component(to)) else {
+            return false;
+        };
+        if from == to {
+            return true;
+        }
+        if to > from {
+            return false;
+        }
+        let mut visited = vec![false; self.count()];
+        let mut stack = vec![from as u32];
+        visited[from] = true;
+        while let Some(component) = stack.pop() {
+            for &next in &self.successors[component as usize] {
+                if next as usize == to {
+                    return true;
+                }
+                if next as usize > to && !visited[next as usize] {
+                    visited[next as usize] = true;
+                    stack.push(next);
+                }
+            }
+        }
+        false
+    }
+}
+
+/// Label every traversable tile of a map with its strongly connected
+/// component, following `Map2D::neighbors` and
+/// `Map2D::knight_neighbors`.
+///
+/// Tiles that are not traversable (see `Map2D::is_traversable`) do not belong
+/// to any component.
+///
+/// # Examples
+///
+/// ```
+/// use std::path::Path;
+/// use movingai::components::label_components;
+/// use movingai::parser::parse_map_file;
+///
+/// let map = parse_map_file(Path::new("./tests/arena.map")).unwrap();
+/// let components = label_components(&map);
+/// assert_eq!(components.component((0, 0)), None);
+/// assert!(components.same_component((1, 11), (1, 12)));
+/// ```
+pub fn label_components<T, M: Map2D<T>>(map: &M) -> Components {
+    let width = map.width();
+    let height = map.height();
+    let index = |(x, y): Coords2D| y * width + x;
+    let coords = |i: usize| (i % width, i / width);
+    let neighbors = |tile: Coords2D| {
+        map.neighbors(tile)
+            .into_iter()
+            .chain(map.knight_neighbors(tile))
+            .filter(|&neighbor| map.is_traversable(neighbor))
+    };
+
+    // Tarjan's algorithm, with an explicit stack to handle large maps.
+    let size = width * height;
+    let mut order = vec![NONE; size];
+    let mut lowlink = vec![NONE; size];
+    let mut on_stack = vec![false; size];
+    let mut stack = Vec::new();
+    let mut labels = vec![NONE; size];
+    let mut sizes = Vec::new();
+    let mut visited = 0;
+    // Every frame is a tile, where its neighbors start in `pending` and the
+    // position in `pending` of the next neighbor to visit. The neighbors are
+    // computed once, when the tile is first visited.
+    let mut frames: Vec<(usize, usize, usize)> = Vec::new();
+    let mut pending: Vec<usize> = Vec::new();
+
+    for root in map.coords() {
+        if order[index(root)] != NONE || !map.is_traversable(root) {
+            continue;
+        }
+        frames.push((index(root), UNVISITED, 0));
+        while let Some(frame) = frames.last_mut() {
+            let tile = frame.0;
+            if frame.1 == UNVISITED {
+                order[tile] = visited;
+                lowlink[tile] = visited;
+                visited += 1;
+                stack.push(tile);
+                on_stack[tile] = true;
+                frame.1 = pending.len();
+                frame.2 = pending.len();
+                pending.extend(neighbors(coords(tile)).map(index));
+            }
+            let start = frame.1;
+            let neighbor = pending.get(frame.2).copied();
+            frame.2 += 1;
+            match neighbor {
+                Some(neighbor) if order[neighbor] == NONE => frames.push((neighbor, UNVISITED, 0)),
+                Some(neighbor) => {
+                    if on_stack[neighbor] {
+                        lowlink[tile] = lowlink[tile].min(order[neighbor]);
+                    }
+                }
+                None => {
+                    frames.pop();
+                    pending.truncate(start);
+                    if let Some(&(parent, ..)) = frames.last() {
+                        lowlink[parent] = lowlink[parent].min(lowlink[tile]);
+                    }
+                    if lowlink[tile] == order[tile] {
+                        let label = sizes.len() as u32;
+                        let mut count = 0;
+                        while let Some(member) = stack.pop() {
+                            on_stack[member] = false;
+                            labels[member] = label;
+                            count += 1;
+                            if member == tile {
+                                break;
+                            }
+                        }
+                        sizes.push(count);
+                    }
+                }
+            }
+        }
+    }
+
+    let mut successors = vec![Vec::new(); sizes.len()];
+    for tile in (0..size).filter(|&tile| labels[tile] != NONE) {
+        for neighbor in neighbors(coords(tile)).map(index) {
+            if labels[neighbor] != labels[tile] {
+                successors[labels[tile] as usize].push(labels[neighbor]);
+            }
+        }
+    }
+    for next in &mut successors {
+        next.sort_unstable();
+        next.dedup();
+    }
+
+    Components {
+        width,
+        height,
+        labels,
+        sizes,
+        successors,
+    }
+}
@@ -0,0 +1,261 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::iter;
+use std::path::{Component, Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use crate::map2d::{MovingAiMap, ParseError, SceneRecord};
+use crate::parser::{parse_map_file, parse_scen_file};
+
+/// A map with the scenario records using it.
+pub type MapRecords = (Arc<MovingAiMap>, Vec<SceneRecord>);
+
+/// An error that can occur when loading a dataset.
+#[derive(Debug)]
+pub enum DatasetError {
+    /// A file could not be parsed.
+    Parse {
+        /// The file.
+        path: PathBuf,
+        /// The parse error.
+        error: ParseError,
+    },
+    /// The map of a scenario cannot be found in the dataset.
+    MapNotFound {
+        /// The scenario file.
+        scenario: PathBuf,
+        /// The map file named by its records.
+        map_file: String,
+    },
+}
+
+impl fmt::Display for DatasetError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DatasetError::Parse { path, error } => write!(f, "{}: {}", path.display(), error),
+            DatasetError::MapNotFound { scenario, map_file } => {
+                write!(f, "{}: map `{}` not found", scenario.display(), map_file)
+            }
+        }
+    }
+}
+
+impl Error for DatasetError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            DatasetError::Parse { error, .. } => Some(error),
+            DatasetError::MapNotFound { .. } => None,
+        }
+    }
+}
+
+/// A collection of `.map` and `.scen` files, laid out like the MovingAI
+/// downloads.
+///
+/// The map of every scenario is resolved from the `map_file` of its records
+/// (see `resolve_map`), and parsed maps are cached, so every map is parsed
+/// once even if many scenarios use it.
+///
+/// # Examples
+///
+/// ```
+/// use movingai::dataset::Dataset;
+/// use movingai::Map2D;
+///
+/// let dataset = Dataset::open("./tests").unwrap();
+/// for entry in dataset.iter() {
+///     match entry {
+///         Ok((map, records)) => {
+///             println!("{} records on a {}x{} map", records.len(), map.width(), map.height())
+///         }
+///         Err(error) => println!("{}", error),
+///     }
+/// }
+/// ```
+#[derive(Debug)]
+pub struct Dataset {
+    root: PathBuf,
+    scenarios: Vec<PathBuf>,
+    maps: Vec<PathBuf>,
+    cache: Mutex<HashMap<PathBuf, Arc<MovingAiMap>>>,
+}
+
+impl Dataset {
+    /// Scan `root` and all its subdirectories for `.map` and `.scen` files.
+    ///
+    /// Symbolic links to files are followed, but symbolic links to
+    /// directories are skipped.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a directory cannot be read.
+    pub fn open<P: AsRef<Path>>(root: P) -> io::Result<Dataset> {
+        let root = root.as_ref().to_path_buf();
+        let mut scenarios = Vec::new();
+        let mut maps = Vec::new();
+        let mut directories = vec![root.clone()];
+        while let Some(directory) = directories.pop() {
+            for entry in fs::read_dir(&directory)? {
+                let entry = entry?;
+                let path = entry.path();
+                if entry.file_type()?.is_dir() {
+                    directories.push(path);
+                } else if path.is_dir() {
+                    // A symbolic link to a directory is not followed, as it
+                    // may point back to one of its ancestors.
+                } else {
+                    match path.extension().and_then(|e| e.to_str()) {
+                        Some("scen") => scenarios.push(path),
+                        Some("map") => maps.push(path),
+                        _ => {}
+                    }
+                }
+            }
+        }
+        scenarios.sort();
+        maps.sort();
+        Ok(Dataset {
+            root,
+            scenarios,
+            maps,
+            cache: Mutex::new(HashMap::new()),
+        })
+    }
+
+    /// The root directory of the dataset.
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// All the `.scen` files of the dataset, sorted.
+    pub fn scenarios(&self) -> &[PathBuf] {
+        &self.scenarios
+    }
+
+    /// All the `.map` files of the dataset, sorted.
+    pub fn maps(&self) -> &[PathBuf] {
+        &self.maps
+    }
+
+    /// Find the map named `map_file` in the records of the scenario
+    /// `scenario`.
+    ///
+    /// `map_file` is first looked up relative to the directory of the
+    /// scenario, then to its ancestors up to the root of the dataset (or
+    /// directly to the root, if the scenario is outside of it). If none
+    /// exists, it falls back on the maps of the dataset with the same file
+    /// name, preferring the one whose path shares more directories with
+    /// `map_file`.
+    ///
+    /// Returns `None` if `map_file` is not a plain relative path (it is
+    /// absolute or contains `..`), so records cannot point outside of the
+    /// dataset.
+    pub fn resolve_map(&self, scenario: &Path, map_file: &str) -> Option<PathBuf> {
+        let relative = Path::new(map_file);
+        let plain = |component| matches!(component, Component::Normal(_) | Component::CurDir);
+        if !relative.components().all(plain) {
+            return None;
+        }
+        let directory = scenario.parent().unwrap_or(Path::new(""));
+        let ancestors = directory
+            .ancestors()
+            .skip(1)
+            .take_while(|ancestor| ancestor.starts_with(&self.root));
+        let root = (!directory.starts_with(&self.root)).then_some(self.root.as_path());
+        for current in iter::once(directory).chain(ancestors).chain(root) {
+            let candidate = current.join(relative);
+            if candidate.is_file() {
+                return Some(candidate);
+            }
+        }
+
+        let name = relative.file_name()?;
+        let shared = |path: &Path| {
+            path.components()
+                .rev()
+                .zip(relative.components().rev())
+                .take_while(|(a, b)| a == b)
+                .count()
+        };
+        self.maps
+            .iter()
+            .filter(|path| path.file_name() == Some(name))
+            .min_by_key(|path| std::cmp::Reverse(shared(path)))
+            .cloned()
+    }
+
+    /// Parse the map at `path`, or return it from the cache if it has
+    /// already been parsed.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the map cannot be parsed.
+    pub fn load_map(&self, path: &Path) -> Result<Arc<MovingAiMap>, DatasetError> {
+        if let Some(map) = self.cache.lock().unwrap().get(path) {
+            return Ok(Arc::clone(map));
+        }
+        let map = parse_map_file(path).map_err(|error| DatasetError::Parse {
+            path: path.to_path_buf(),
+            error,
+        })?;
+        let map = Arc::new(map);
+        self.cache
+            .lock()
+            .unwrap()
+            .insert(path.to_path_buf(), Arc::clone(&map));
+        Ok(map)
+    }
+
+    /// Parse the scenario at `path` and load its maps.
+    ///
+    /// # Returns
+    ///  The records of the scenario grouped by map, in the order in which the
+    ///  maps first appear. MovingAI scenarios use a single map.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the scenario or a map cannot be parsed, or if a map
+    /// cannot be found.
+    pub fn load_scenario(&self, path: &Path) -> Result<Vec<MapRecords>, DatasetError> {
+        let records = parse_scen_file(path).map_err(|error| DatasetError::Parse {
+            path: path.to_path_buf(),
+            error,
+        })?;
+        let mut groups: Vec<(String, Vec<SceneRecord>)> = Vec::new();
+        for record in records {
+            match groups.iter_mut().find(|(name, _)| *name == record.map_file) {
+                Some((_, group)) => group.push(record),
+                None => groups.push((record.map_file.clone(), vec![record])),
+            }
+        }
+        groups
+            .into_iter()
+            .map(|(map_file, records)| {
+                let map_path =
+                    self.resolve_map(path, &map_file)
+                        .ok_or_else(|| DatasetError::MapNotFound {
+                            scenario: path.to_path_buf(),
+                            map_file,
+                        })?;
+                Ok((self.load_map(&map_path)?, records))
+            })
+            .collect()
+    }
+
+    /// Iterate over the scenarios of the dataset, yielding every map with its
+    /// records.
+    ///
+    /// Scenarios that cannot be loaded yield an error, and the iteration
+    /// continues with the next one.
+    pub fn iter(&self) -> impl Iterator<Item = Result<MapRecords, DatasetError>> + '_ {
+        self.scenarios
+            .iter()
+            .flat_map(|path| match self.load_scenario(path) {
+                Ok(groups) => groups.into_iter().map(Ok).collect::<Vec<_>>(),
+                Err(error) => vec![Err(error)],
+            })
+    }
+}
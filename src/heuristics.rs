@@ -0,0 +1,134 @@
+use std::f64::consts::SQRT_2;
+
+use crate::map2d::{Coords2D, MapType};
+
+/// A heuristic estimating the cost between two coordinates.
+pub type Heuristic = fn(Coords2D, Coords2D) -> f64;
+
+/// The octile distance: the cost of the shortest path on an empty octile
+/// map, where diagonal steps cost `sqrt(2)`.
+///
+/// # Examples
+///
+/// ```
+/// use movingai::heuristics::octile;
+///
+/// assert_eq!(octile((0, 0), (3, 0)), 3.0);
+/// assert_eq!(octile((0, 0), (1, 1)), 2f64.sqrt());
+/// ```
+pub fn octile(a: Coords2D, b: Coords2D) -> f64 {
+    let dx = a.0.abs_diff(b.0) as f64;
+    let dy = a.1.abs_diff(b.1) as f64;
+    dx.max(dy) + (SQRT_2 - 1.0) * dx.min(dy)
+}
+
+/// The Manhattan distance: the cost of the shortest path on an empty
+/// 4-connected map.
+///
+/// # Examples
+///
+/// ```
+/// use movingai::heuristics::manhattan;
+///
+/// assert_eq!(manhattan((0, 0), (3, 4)), 7.0);
+/// ```
+pub fn manhattan(a: Coords2D, b: Coords2D) -> f64 {
+    (a.0.abs_diff(b.0) + a.1.abs_diff(b.1)) as f64
+}
+
+/// The Euclidean distance.
+///
+/// It is admissible on both octile and 4-connected maps, but weaker than
+/// `octile` and `manhattan`.
+///
+/// # Examples
+///
+/// ```
+/// use movingai::heuristics::euclidean;
+///
+/// assert_eq!(euclidean((0, 0), (3, 4)), 5.0);
+/// ```
+pub fn euclidean(a: Coords2D, b: Coords2D) -> f64 {
+    let dx = a.0.abs_diff(b.0) as f64;
+    let dy = a.1.abs_diff(b.1) as f64;
+    dx.hypot(dy)
+}
+
+/// The Chebyshev distance: the cost of the shortest path on an empty map
+/// where diagonal steps cost `1`.
+///
+/// It is admissible on octile maps, but weaker than `octile`.
+///
+/// # Examples
+///
+/// ```
+/// use movingai::heuristics::chebyshev;
+///
+/// assert_eq!(chebyshev((0, 0), (3, 4)), 4.0);
+/// ```
+pub fn chebyshev(a: Coords2D, b: Coords2D) -> f64 {
+    a.0.abs_diff(b.0).max(a.1.abs_diff(b.1)) as f64
+}
+
+/// The hex distance between two tiles of a `MapType::Hex` map: the cost of
+/// the shortest path on an empty hex map.
+///
+/// # Examples
+///
+/// ```
+/// use movingai::heuristics::hex;
+///
+/// assert_eq!(hex((0, 0), (2, 2)), 4.0);
+/// assert_eq!(hex((2, 0), (0, 2)), 2.0);
+/// ```
+pub fn hex(a: Coords2D, b: Coords2D) -> f64 {
+    let dx = b.0 as i64 - a.0 as i64;
+    let dy = b.1 as i64 - a.1 as i64;
+    dx.abs().max(dy.abs()).max((dx + dy).abs()) as f64
+}
+
+/// The cost of the shortest path on an empty `MapType::SixteenConnected`
+/// map, where knight moves cost `sqrt(5)`.
+///
+/// # Examples
+///
+/// ```
+/// use movingai::heuristics::sixteen_connected;
+///
+/// assert_eq!(sixteen_connected((0, 0), (2, 1)), 5f64.sqrt());
+/// assert_eq!(sixteen_connected((0, 0), (3, 0)), 3.0);
+/// ```
+pub fn sixteen_connected(a: Coords2D, b: Coords2D) -> f64 {
+    let dx = a.0.abs_diff(b.0);
+    let dy = a.1.abs_diff(b.1);
+    let (long, short) = (dx.max(dy) as f64, dx.min(dy) as f64);
+    // The path combines the two kinds of steps around its direction: knight
+    // moves with straight steps, or knight moves with diagonal steps.
+    if 2.0 * short <= long {
+        short * 5f64.sqrt() + (long - 2.0 * short)
+    } else {
+        (long - short) * 5f64.sqrt() + (2.0 * short - long) * SQRT_2
+    }
+}
+
+/// The most informed admissible and consistent heuristic for maps of the
+/// given type: `octile` for octile maps, `manhattan` for 4-connected ones,
+/// `hex` for hex maps and `sixteen_connected` for 16-connected ones.
+///
+/// # Examples
+///
+/// ```
+/// use movingai::heuristics::for_map_type;
+/// use movingai::MapType;
+///
+/// let h = for_map_type(MapType::FourConnected);
+/// assert_eq!(h((0, 0), (1, 1)), 2.0);
+/// ```
+pub fn for_map_type(map_type: MapType) -> Heuristic {
+    match map_type {
+        MapType::Octile => octile,
+        MapType::FourConnected => manhattan,
+        MapType::Hex => hex,
+        MapType::SixteenConnected => sixteen_connected,
+    }
+}
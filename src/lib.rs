@@ -12,10 +12,39 @@
 //! Things.
 
 
+/// Contains tools to benchmark solvers on scenarios.
+pub mod bench;
+
+/// Contains the connected components of maps.
+pub mod components;
+
+/// Contains tools to load whole MovingAI datasets.
+pub mod dataset;
+
+/// Contains distance heuristics for grid maps.
+pub mod heuristics;
+
 /// Contains all the parser functions.
 pub mod parser;
 
+/// Contains tools to draw maps.
+pub mod render;
+
+/// Contains tools to generate scenarios.
+pub mod scenario;
+
+/// Contains shortest path algorithms.
+pub mod search;
+
 mod map2d;
+mod rules;
+mod tile;
 
 /// Contains data structure for 2D MovingAI maps.
 pub use map2d::*;
+
+/// Contains the rules deciding which tiles can be traversed.
+pub use rules::*;
+
+/// Contains the tiles of MovingAI maps.
+pub use tile::*;
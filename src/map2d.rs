@@ -1,21 +1,32 @@
-use std::{error::Error, fmt, ops::Index};
+use std::{error::Error, fmt, io, io::Write, ops::Index};
 
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 
 use arrayvec::ArrayVec;
 
+use crate::heuristics;
+use crate::rules::{DiagonalPolicy, MovingAiRules, TraversalRules};
+use crate::tile::Tile;
+
 /// Store coordinates in the (x,y) format.
 pub type Coords2D = (usize, usize);
 
-/// Internal enum representing the type of map connectivity.
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// The connectivity of a map, from the `type` header of a `.map` file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
-enum MapType {
+pub enum MapType {
     /// 8-connected grid (diagonal movement allowed)
     Octile,
     /// 4-connected grid (only cardinal directions)
     FourConnected,
+    /// 6-connected hex grid in axial coordinates: the neighbors of `(x, y)`
+    /// are `(x ± 1, y)`, `(x, y ± 1)`, `(x + 1, y - 1)` and `(x - 1, y + 1)`.
+    /// Every step costs `1`.
+    Hex,
+    /// 16-connected grid: the 8-connected neighbors plus the eight "knight
+    /// moves" (`(±1, ±2)` and `(±2, ±1)`), costing `sqrt(5)`.
+    SixteenConnected,
 }
 
 impl MapType {
@@ -24,11 +35,65 @@ impl MapType {
     fn from_string(s: &str) -> Self {
         match s.to_lowercase().as_str() {
             "octile" => MapType::Octile,
+            "hex" => MapType::Hex,
+            "sixteen-connected" => MapType::SixteenConnected,
             _ => MapType::FourConnected,
         }
     }
+
+    /// Check if `s` is the name of a supported map type.
+    pub(crate) fn is_known(s: &str) -> bool {
+        matches!(
+            s.to_lowercase().as_str(),
+            "octile" | "four-connected" | "hex" | "sixteen-connected"
+        )
+    }
+
+    /// The name used in the `type` header of a `.map` file.
+    fn as_str(&self) -> &'static str {
+        match self {
+            MapType::Octile => "octile",
+            MapType::FourConnected => "four-connected",
+            MapType::Hex => "hex",
+            MapType::SixteenConnected => "sixteen-connected",
+        }
+    }
+
+    /// The offsets of the neighbors of a tile, except the knight moves of
+    /// 16-connected maps (see `KNIGHT_OFFSETS`).
+    fn offsets(&self) -> &'static [(isize, isize)] {
+        const OFFSETS: [(isize, isize); 8] = [
+            (1, 0),
+            (-1, 0),
+            (0, 1),
+            (0, -1),
+            (1, 1),
+            (1, -1),
+            (-1, 1),
+            (-1, -1),
+        ];
+        const HEX_OFFSETS: [(isize, isize); 6] =
+            [(1, 0), (-1, 0), (0, 1), (0, -1), (1, -1), (-1, 1)];
+        match self {
+            MapType::FourConnected => &OFFSETS[..4],
+            MapType::Octile | MapType::SixteenConnected => &OFFSETS,
+            MapType::Hex => &HEX_OFFSETS,
+        }
+    }
 }
 
+/// The offsets of the knight moves of 16-connected maps.
+const KNIGHT_OFFSETS: [(isize, isize); 8] = [
+    (2, 1),
+    (2, -1),
+    (-2, 1),
+    (-2, -1),
+    (1, 2),
+    (1, -2),
+    (-1, 2),
+    (-1, -2),
+];
+
 /// A trait representing common operations that can be performed on 2D Maps
 /// representations.
 pub trait Map2D<T> {
@@ -60,6 +125,27 @@ pub trait Map2D<T> {
     /// ```
     fn get(&self, coords: Coords2D) -> &T;
 
+    /// Return a copy of the tile at the given coordinates.
+    ///
+    /// It is the same as `*map.get(coords)`, but maps that do not store
+    /// their tiles as `T` (such as `MovingAiMap`) can skip the reference.
+    ///
+    /// ## Examples:
+    ///
+    /// ```rust
+    /// use movingai::Map2D;
+    /// use movingai::MovingAiMap;
+    ///
+    /// let mm = MovingAiMap::new(String::from("octile"), 1, 2, vec!['.', 'W']).unwrap();
+    /// assert_eq!(mm.get_copied((1, 0)), 'W')
+    /// ```
+    fn get_copied(&self, coords: Coords2D) -> T
+    where
+        T: Copy,
+    {
+        *self.get(coords)
+    }
+
     /// Check if the given coordinates are out of bound.
     ///
     /// # Examples
@@ -124,14 +210,227 @@ pub trait Map2D<T> {
     fn free_states(&self) -> usize;
 
     /// Return the list of accessible neighbors of a tile.
+    ///
+    /// On 16-connected maps these are **only the 8 adjacent tiles**. Callers
+    /// exploring a 16-connected map must also chain `knight_neighbors`, or
+    /// they will explore an 8-connected graph:
+    ///
+    /// ```
+    /// use movingai::{Map2D, MovingAiMap};
+    ///
+    /// let map = MovingAiMap::new(String::from("sixteen-connected"), 5, 5, vec!['.'; 25]).unwrap();
+    /// assert_eq!(map.neighbors((2, 2)).len(), 8);
+    /// let all = map.neighbors((2, 2)).into_iter().chain(map.knight_neighbors((2, 2)));
+    /// assert_eq!(all.count(), 16);
+    /// ```
     fn neighbors(&self, tile: Coords2D) -> ArrayVec<Coords2D, 8>;
+
+    /// Return the list of accessible tiles reached from a tile with a knight
+    /// move (`(±1, ±2)` or `(±2, ±1)`).
+    ///
+    /// Only 16-connected maps have knight moves. By default, the list is
+    /// empty. The searches visit both `neighbors` and `knight_neighbors`.
+    fn knight_neighbors(&self, _tile: Coords2D) -> ArrayVec<Coords2D, 8> {
+        ArrayVec::new()
+    }
+
+    /// The cost of moving from `from` to the adjacent tile `to`.
+    ///
+    /// By default, it is the length of the step: `1` for cardinal steps and
+    /// `sqrt(2)` for diagonal ones.
+    fn step_cost(&self, from: Coords2D, to: Coords2D) -> f64 {
+        let dx = from.0.abs_diff(to.0) as f64;
+        let dy = from.1.abs_diff(to.1) as f64;
+        dx.hypot(dy)
+    }
+
+    /// An estimate of the cost of a path from `from` to `to`, used as
+    /// heuristic by the searches.
+    ///
+    /// It must be consistent for the searches to return optimal paths: for
+    /// every step from `a` to `b`, `heuristic(a, c)` must not exceed
+    /// `step_cost(a, b) + heuristic(b, c)`. Never overestimating the cost is
+    /// not enough, because the searches (`astar`, `jps` and `jps_plus`)
+    /// never reopen closed nodes. By default, it is the octile distance.
+    fn heuristic(&self, from: Coords2D, to: Coords2D) -> f64 {
+        heuristics::octile(from, to)
+    }
 }
 
 #[derive(Debug)]
-/// An error that can occur when parsing a map.
+/// An error that can occur when parsing a map or a scenario.
+///
+/// Errors pointing at a specific place of the input carry the line and the
+/// column (both 1-based) where the problem has been found.
 pub enum ParseError {
-    /// The size of the map does not match the provided height * width.
+    /// The size of the map does not match the provided height * width, or
+    /// one of them is zero.
     InvalidMapSize,
+    /// The input could not be read.
+    Io(io::Error),
+    /// A tile cannot be stored in the map. Tiles must be one-byte characters
+    /// (`'\u{0}'..='\u{ff}'`) other than the line breaks `'\n'` and `'\r'`.
+    /// Unlike `UnknownTile`, this is an error even in lenient mode.
+    UnsupportedTile {
+        /// The coordinates of the tile.
+        coords: Coords2D,
+        /// The offending character.
+        tile: char,
+        /// The line of the tile, if it has been parsed.
+        line: Option<usize>,
+        /// The column of the tile, if it has been parsed.
+        column: Option<usize>,
+    },
+    /// A header key (`type`, `height`, `width` or `map`) is missing. A
+    /// missing `type` is only a warning in lenient mode.
+    MissingHeader {
+        /// The missing key.
+        key: &'static str,
+        /// The line where the key was expected.
+        line: usize,
+        /// The column where the key was expected.
+        column: usize,
+    },
+    /// A numeric field does not contain a valid number.
+    InvalidNumber {
+        /// The name of the field (e.g., `height` or `start x`).
+        field: &'static str,
+        /// The line of the field.
+        line: usize,
+        /// The column where the field starts.
+        column: usize,
+        /// The offending text.
+        text: String,
+    },
+    /// A map row contains a character that is not a MovingAI tile.
+    UnknownTile {
+        /// The line of the row.
+        line: usize,
+        /// The column of the tile.
+        column: usize,
+        /// The offending character.
+        tile: char,
+    },
+    /// A map row is shorter than the map width.
+    ShortRow {
+        /// The index of the row in the map (its `y` coordinate).
+        row: usize,
+        /// The line of the row.
+        line: usize,
+        /// The column where the row ends too early.
+        column: usize,
+        /// The expected row length (the map width).
+        expected: usize,
+        /// The actual row length.
+        found: usize,
+        /// The offending row.
+        text: String,
+    },
+    /// A map row is longer than the map width.
+    LongRow {
+        /// The index of the row in the map (its `y` coordinate).
+        row: usize,
+        /// The line of the row.
+        line: usize,
+        /// The column of the first tile exceeding the width.
+        column: usize,
+        /// The expected row length (the map width).
+        expected: usize,
+        /// The actual row length.
+        found: usize,
+        /// The offending row.
+        text: String,
+    },
+    /// The map has less rows than its height.
+    MissingRows {
+        /// The line where the next row was expected.
+        line: usize,
+        /// The column where the next row was expected.
+        column: usize,
+        /// The expected number of rows (the map height).
+        expected: usize,
+        /// The number of rows found.
+        found: usize,
+    },
+    /// A header line is not one of `type`, `height` or `width`.
+    UnknownHeader {
+        /// The line of the header.
+        line: usize,
+        /// The column of the unexpected token.
+        column: usize,
+        /// The offending line.
+        text: String,
+    },
+    /// The `type` header is not a supported map type.
+    UnknownMapType {
+        /// The line of the header.
+        line: usize,
+        /// The column of the type name.
+        column: usize,
+        /// The offending type name.
+        text: String,
+    },
+    /// A non-empty line follows the last row of the map.
+    TrailingData {
+        /// The line of the unexpected content.
+        line: usize,
+        /// The column of the unexpected content.
+        column: usize,
+        /// The offending line.
+        text: String,
+    },
+    /// A scenario record does not have the expected number of fields.
+    WrongFieldCount {
+        /// The line of the record.
+        line: usize,
+        /// The column where the record ends, or where its first extra field
+        /// starts.
+        column: usize,
+        /// The expected number of fields.
+        expected: usize,
+        /// The number of fields found in the record.
+        found: usize,
+        /// The offending record.
+        text: String,
+    },
+}
+
+impl ParseError {
+    /// The line (1-based) where the error has been found, if any.
+    pub fn line(&self) -> Option<usize> {
+        match self {
+            ParseError::InvalidMapSize | ParseError::Io(_) => None,
+            ParseError::UnsupportedTile { line, .. } => *line,
+            ParseError::MissingHeader { line, .. }
+            | ParseError::InvalidNumber { line, .. }
+            | ParseError::UnknownTile { line, .. }
+            | ParseError::ShortRow { line, .. }
+            | ParseError::LongRow { line, .. }
+            | ParseError::MissingRows { line, .. }
+            | ParseError::UnknownHeader { line, .. }
+            | ParseError::UnknownMapType { line, .. }
+            | ParseError::TrailingData { line, .. }
+            | ParseError::WrongFieldCount { line, .. } => Some(*line),
+        }
+    }
+
+    /// The column (1-based) where the error has been found, if any.
+    pub fn column(&self) -> Option<usize> {
+        match self {
+            ParseError::InvalidMapSize | ParseError::Io(_) => None,
+            ParseError::UnsupportedTile { column, .. } => *column,
+            ParseError::MissingHeader { column, .. }
+            | ParseError::InvalidNumber { column, .. }
+            | ParseError::UnknownTile { column, .. }
+            | ParseError::ShortRow { column, .. }
+            | ParseError::LongRow { column, .. }
+            | ParseError::MissingRows { column, .. }
+            | ParseError::UnknownHeader { column, .. }
+            | ParseError::UnknownMapType { column, .. }
+            | ParseError::TrailingData { column, .. }
+            | ParseError::WrongFieldCount { column, .. } => Some(*column),
+        }
+    }
 }
 
 impl fmt::Display for ParseError {
@@ -140,20 +439,285 @@ impl fmt::Display for ParseError {
             ParseError::InvalidMapSize => {
                 write!(f, "Map size does not match the provided height * width")
             }
+            ParseError::Io(e) => write!(f, "I/O error: {}", e),
+            ParseError::UnsupportedTile {
+                coords,
+                tile,
+                line: Some(line),
+                column: Some(column),
+            } => write!(
+                f,
+                "line {}, column {}: tile {:?} at {:?} cannot be stored in a map",
+                line, column, tile, coords
+            ),
+            ParseError::UnsupportedTile { coords, tile, .. } => {
+                write!(
+                    f,
+                    "tile {:?} at {:?} cannot be stored in a map",
+                    tile, coords
+                )
+            }
+            ParseError::MissingHeader { key, line, column } => {
+                write!(
+                    f,
+                    "line {}, column {}: missing `{}` header",
+                    line, column, key
+                )
+            }
+            ParseError::InvalidNumber {
+                field,
+                line,
+                column,
+                text,
+            } => write!(
+                f,
+                "line {}, column {}: invalid {} `{}`",
+                line, column, field, text
+            ),
+            ParseError::UnknownTile { line, column, tile } => {
+                write!(
+                    f,
+                    "line {}, column {}: unknown tile `{}`",
+                    line, column, tile
+                )
+            }
+            ParseError::ShortRow {
+                row,
+                line,
+                column,
+                expected,
+                found,
+                text,
+            }
+            | ParseError::LongRow {
+                row,
+                line,
+                column,
+                expected,
+                found,
+                text,
+            } => write!(
+                f,
+                "line {}, column {}: row {} has {} tiles, expected {} (`{}`)",
+                line, column, row, found, expected, text
+            ),
+            ParseError::MissingRows {
+                line,
+                column,
+                expected,
+                found,
+            } => write!(
+                f,
+                "line {}, column {}: map has {} rows, expected {}",
+                line, column, found, expected
+            ),
+            ParseError::UnknownHeader { line, column, text } => {
+                write!(
+                    f,
+                    "line {}, column {}: unknown header `{}`",
+                    line, column, text
+                )
+            }
+            ParseError::UnknownMapType { line, column, text } => {
+                write!(
+                    f,
+                    "line {}, column {}: unknown map type `{}`",
+                    line, column, text
+                )
+            }
+            ParseError::TrailingData { line, column, text } => write!(
+                f,
+                "line {}, column {}: unexpected data after the map (`{}`)",
+                line, column, text
+            ),
+            ParseError::WrongFieldCount {
+                line,
+                column,
+                expected,
+                found,
+                text,
+            } => write!(
+                f,
+                "line {}, column {}: expected {} fields, found {} (`{}`)",
+                line, column, expected, found, text
+            ),
         }
     }
 }
 
-impl Error for ParseError {}
+impl Error for ParseError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            ParseError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for ParseError {
+    fn from(e: io::Error) -> Self {
+        ParseError::Io(e)
+    }
+}
+
+/// The reason why a path is not valid on a map.
+///
+/// Every error points at the first illegal position of the path with `index`:
+/// the step from `path[index - 1]` to `path[index]` (or the start, if `index`
+/// is `0`) is not allowed.
+#[derive(Debug, Clone, PartialEq)]
+pub enum PathError {
+    /// The path has no tiles.
+    Empty,
+    /// A tile of the path is out of the map.
+    OutOfBounds {
+        /// The position of the tile in the path.
+        index: usize,
+        /// The coordinates of the tile.
+        coords: Coords2D,
+    },
+    /// A tile of the path cannot be traversed at all.
+    Blocked {
+        /// The position of the tile in the path.
+        index: usize,
+        /// The coordinates of the tile.
+        coords: Coords2D,
+        /// The tile.
+        tile: Tile,
+    },
+    /// Two consecutive tiles of the path are not adjacent on the map (or they
+    /// are the same tile).
+    NotAdjacent {
+        /// The position of the second tile in the path.
+        index: usize,
+        /// The first tile of the step.
+        from: Coords2D,
+        /// The second tile of the step.
+        to: Coords2D,
+    },
+    /// The terrain rules do not allow to move between two tiles (e.g., from
+    /// `.` to `W`).
+    IllegalTransition {
+        /// The position of the second tile in the path.
+        index: usize,
+        /// The first tile of the step.
+        from: Coords2D,
+        /// The second tile of the step.
+        to: Coords2D,
+        /// The tile at `from`.
+        from_tile: Tile,
+        /// The tile at `to`.
+        to_tile: Tile,
+    },
+    /// A diagonal step cuts a corner not allowed by the diagonal policy.
+    CornerCut {
+        /// The position of the second tile in the path.
+        index: usize,
+        /// The first tile of the step.
+        from: Coords2D,
+        /// The second tile of the step.
+        to: Coords2D,
+    },
+}
+
+impl PathError {
+    /// The position in the path of the first illegal tile, if any.
+    pub fn index(&self) -> Option<usize> {
+        match self {
+            PathError::Empty => None,
+            PathError::OutOfBounds { index, .. }
+            | PathError::Blocked { index, .. }
+            | PathError::NotAdjacent { index, .. }
+            | PathError::IllegalTransition { index, .. }
+            | PathError::CornerCut { index, .. } => Some(*index),
+        }
+    }
+}
+
+impl fmt::Display for PathError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PathError::Empty => write!(f, "the path is empty"),
+            PathError::OutOfBounds { index, coords } => {
+                write!(f, "tile {}: {:?} is out of the map", index, coords)
+            }
+            PathError::Blocked {
+                index,
+                coords,
+                tile,
+            } => write!(
+                f,
+                "tile {}: {:?} (`{}`) cannot be traversed",
+                index,
+                coords,
+                char::from(*tile)
+            ),
+            PathError::NotAdjacent { index, from, to } => {
+                write!(f, "tile {}: {:?} is not adjacent to {:?}", index, to, from)
+            }
+            PathError::IllegalTransition {
+                index,
+                from,
+                to,
+                from_tile,
+                to_tile,
+            } => write!(
+                f,
+                "tile {}: cannot move from {:?} (`{}`) to {:?} (`{}`)",
+                index,
+                from,
+                char::from(*from_tile),
+                to,
+                char::from(*to_tile)
+            ),
+            PathError::CornerCut { index, from, to } => write!(
+                f,
+                "tile {}: moving from {:?} to {:?} cuts a corner",
+                index, from, to
+            ),
+        }
+    }
+}
+
+impl Error for PathError {}
+
+/// Every one-byte character indexed by its value. Tiles are stored as bytes
+/// and this table lets `get` hand out a `&char` for them.
+static TILE_CHARS: [char; 256] = {
+    let mut chars = ['\0'; 256];
+    let mut i = 0;
+    while i < 256 {
+        chars[i] = i as u8 as char;
+        i += 1;
+    }
+    chars
+};
+
+/// The byte storing `tile`, if it can be stored in a map and written in a
+/// `.map` file.
+pub(crate) fn tile_to_byte(tile: char) -> Option<u8> {
+    match tile {
+        '\n' | '\r' => None,
+        _ => u8::try_from(tile).ok(),
+    }
+}
 
 /// An immutable representation of a MovingAI map.
-#[derive(Debug)]
+///
+/// Tiles are stored using one byte each. Which tiles can be traversed is
+/// decided by the `TraversalRules` `R`, by default the MovingAI ones.
+#[derive(Debug, Clone, PartialEq)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
-pub struct MovingAiMap {
+pub struct MovingAiMap<R = MovingAiRules> {
     map_type: MapType,
     height: usize,
     width: usize,
-    map: Box<[char]>,
+    #[cfg_attr(feature = "serde", serde(with = "tiles_serde"))]
+    map: Box<[u8]>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    diagonal_policy: DiagonalPolicy,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    rules: R,
 }
 
 impl MovingAiMap {
@@ -167,14 +731,17 @@ impl MovingAiMap {
     ///
     /// # Errors
     ///
-    /// Returns an error if the size of the map vector is different from `height * width`.
+    /// Returns an error if `height` or `width` is zero, if the size of the map
+    /// vector is different from `height * width` or if a tile is not a
+    /// one-byte character or is a line break (see
+    /// `ParseError::UnsupportedTile`).
     pub fn new(
         map_type: String,
         height: usize,
         width: usize,
         map: Vec<char>,
     ) -> Result<MovingAiMap, ParseError> {
-        if map.len() != height * width {
+        if height == 0 || width == 0 || height.checked_mul(width) != Some(map.len()) {
             return Err(ParseError::InvalidMapSize);
         }
         MovingAiMap::new_from_slice(map_type, height, width, map.into_boxed_slice())
@@ -190,23 +757,299 @@ impl MovingAiMap {
     ///
     /// # Errors
     ///
-    /// Returns an error if the size of the map vector is different from `height * width`.
+    /// Returns an error if `height` or `width` is zero, if the size of the map
+    /// vector is different from `height * width` or if a tile is not a
+    /// one-byte character or is a line break (see
+    /// `ParseError::UnsupportedTile`).
+    #[allow(clippy::boxed_local)] // Kept boxed for backward compatibility.
     pub fn new_from_slice(
         map_type: String,
         height: usize,
         width: usize,
         map: Box<[char]>,
     ) -> Result<MovingAiMap, ParseError> {
-        if map.len() != height * width {
+        if height == 0 || width == 0 || height.checked_mul(width) != Some(map.len()) {
             return Err(ParseError::InvalidMapSize);
         }
+        let map = map
+            .iter()
+            .enumerate()
+            .map(|(i, &tile)| {
+                tile_to_byte(tile).ok_or(ParseError::UnsupportedTile {
+                    coords: (i % width, i / width),
+                    tile,
+                    line: None,
+                    column: None,
+                })
+            })
+            .collect::<Result<_, _>>()?;
         Ok(MovingAiMap {
             map_type: MapType::from_string(&map_type),
             height,
             width,
             map,
+            diagonal_policy: DiagonalPolicy::default(),
+            rules: MovingAiRules,
         })
     }
+}
+
+impl<R: TraversalRules> MovingAiMap<R> {
+    /// Replace the traversal rules of the map.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use movingai::{Map2D, MovingAiMap, UniformRules};
+    ///
+    /// let map = MovingAiMap::new(String::from("octile"), 1, 2, vec!['.', 'W']).unwrap();
+    /// assert!(!map.is_traversable_from((0, 0), (1, 0)));
+    /// let map = map.with_rules(UniformRules);
+    /// assert!(map.is_traversable_from((0, 0), (1, 0)));
+    /// ```
+    pub fn with_rules<S: TraversalRules>(self, rules: S) -> MovingAiMap<S> {
+        MovingAiMap {
+            map_type: self.map_type,
+            height: self.height,
+            width: self.width,
+            map: self.map,
+            diagonal_policy: self.diagonal_policy,
+            rules,
+        }
+    }
+
+    /// The traversal rules of the map.
+    pub fn rules(&self) -> &R {
+        &self.rules
+    }
+
+    /// The connectivity of the map.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use movingai::{MapType, MovingAiMap};
+    ///
+    /// let map = MovingAiMap::new(String::from("octile"), 1, 1, vec!['.']).unwrap();
+    /// assert_eq!(map.map_type(), MapType::Octile);
+    /// ```
+    pub fn map_type(&self) -> MapType {
+        self.map_type
+    }
+
+    /// Replace the policy for diagonal steps of the map.
+    ///
+    /// The policy is used by `is_traversable_from` (and then `neighbors`)
+    /// on octile and 16-connected maps.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use movingai::{DiagonalPolicy, Map2D, MovingAiMap};
+    ///
+    /// let map = MovingAiMap::new(String::from("octile"), 2, 2, vec!['.', 'T', '.', '.']).unwrap();
+    /// assert!(!map.is_traversable_from((0, 0), (1, 1)));
+    /// let map = map.with_diagonal_policy(DiagonalPolicy::OneSideFree);
+    /// assert!(map.is_traversable_from((0, 0), (1, 1)));
+    /// ```
+    pub fn with_diagonal_policy(mut self, policy: DiagonalPolicy) -> Self {
+        self.diagonal_policy = policy;
+        self
+    }
+
+    /// The policy for diagonal steps of the map.
+    pub fn diagonal_policy(&self) -> DiagonalPolicy {
+        self.diagonal_policy
+    }
+
+    /// Write the map in the MovingAI `.map` format.
+    ///
+    /// The output is made of the `type`, `height`, `width` and `map` headers
+    /// followed by one line for every row of the map. Parsing it gives back
+    /// the same type, size and tiles; the diagonal policy and the traversal
+    /// rules are not part of the format and are not written.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if it is not possible to write on `writer`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use movingai::MovingAiMap;
+    ///
+    /// let map = MovingAiMap::new(String::from("octile"), 2, 3, vec!['.'; 6]).unwrap();
+    /// let mut out = Vec::new();
+    /// map.write_to(&mut out).unwrap();
+    /// assert_eq!(out, b"type octile\nheight 2\nwidth 3\nmap\n...\n...\n");
+    /// ```
+    pub fn write_to<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        writeln!(writer, "type {}", self.map_type.as_str())?;
+        writeln!(writer, "height {}", self.height)?;
+        writeln!(writer, "width {}", self.width)?;
+        writeln!(writer, "map")?;
+        for row in self.map.chunks(self.width) {
+            let line: String = row.iter().map(|&tile| tile as char).collect();
+            writeln!(writer, "{}", line)?;
+        }
+        Ok(())
+    }
+
+    /// Return the tile at the given coordinates.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `coords` is out of bound.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use movingai::{MovingAiMap, Tile};
+    ///
+    /// let map = MovingAiMap::new(String::from("octile"), 1, 2, vec!['.', 'W']).unwrap();
+    /// assert_eq!(map.tile((1, 0)), Tile::Water);
+    /// ```
+    pub fn tile(&self, coords: Coords2D) -> Tile {
+        Tile::from(self.tile_byte(coords) as char)
+    }
+
+    /// Check that `path` can be followed on the map and return its cost.
+    ///
+    /// Every step must be allowed by `is_traversable_from`, so it follows the
+    /// traversal rules and the diagonal policy of the map. Cardinal steps cost
+    /// `1` and diagonal steps cost `sqrt(2)`, as in the MovingAI optimal
+    /// lengths.
+    ///
+    /// # Errors
+    ///
+    /// Returns the first illegal tile or step of the path, and why it is
+    /// illegal.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use movingai::{MovingAiMap, PathError};
+    ///
+    /// let map = MovingAiMap::new(String::from("octile"), 2, 3, vec!['.', '.', '.', 'T', '.', '.']).unwrap();
+    /// assert_eq!(map.validate_path(&[(0, 0), (1, 0), (2, 1)]), Ok(1.0 + 2f64.sqrt()));
+    /// assert_eq!(
+    ///     map.validate_path(&[(0, 0), (1, 1)]),
+    ///     Err(PathError::CornerCut { index: 1, from: (0, 0), to: (1, 1) })
+    /// );
+    /// ```
+    pub fn validate_path(&self, path: &[Coords2D]) -> Result<f64, PathError> {
+        let Some(&start) = path.first() else {
+            return Err(PathError::Empty);
+        };
+        self.check_tile(0, start)?;
+        let mut cost = 0.0;
+        for (index, step) in path.windows(2).enumerate() {
+            let index = index + 1;
+            let (from, to) = (step[0], step[1]);
+            self.check_tile(index, to)?;
+            if from == to || !self.coordinates_connect(from, to) {
+                return Err(PathError::NotAdjacent { index, from, to });
+            }
+            if !self.is_traversable_from(from, to) {
+                let from_tile = self.tile(from);
+                let to_tile = self.tile(to);
+                let diagonal = from.0 != to.0 && from.1 != to.1;
+                if diagonal && self.rules.can_move(from_tile, to_tile) {
+                    return Err(PathError::CornerCut { index, from, to });
+                }
+                return Err(PathError::IllegalTransition {
+                    index,
+                    from,
+                    to,
+                    from_tile,
+                    to_tile,
+                });
+            }
+            cost += self.step_cost(from, to);
+        }
+        Ok(cost)
+    }
+
+    /// Check that the tile in position `index` of a path can be traversed.
+    fn check_tile(&self, index: usize, coords: Coords2D) -> Result<(), PathError> {
+        if self.is_out_of_bound(coords) {
+            return Err(PathError::OutOfBounds { index, coords });
+        }
+        if !self.is_traversable(coords) {
+            return Err(PathError::Blocked {
+                index,
+                coords,
+                tile: self.tile(coords),
+            });
+        }
+        Ok(())
+    }
+
+    /// The raw byte of the tile at `coords`.
+    pub(crate) fn tile_byte(&self, coords: Coords2D) -> u8 {
+        self.map[coords.1 * self.width + coords.0]
+    }
+
+    /// Check if the agent can step from `from` to `to`. Both tiles must be
+    /// in bounds and `to` must be adjacent to `from`.
+    fn can_step(&self, from: Coords2D, to: Coords2D) -> bool {
+        let diagonal = from.0 != to.0 && from.1 != to.1;
+        let to_tile = self.tile(to);
+        let from_tile = self.tile(from);
+        match (self.map_type, diagonal) {
+            (MapType::FourConnected | MapType::Hex, _)
+            | (MapType::Octile | MapType::SixteenConnected, false) => {
+                self.rules.can_move(from_tile, to_tile)
+            }
+            (MapType::Octile | MapType::SixteenConnected, true) => {
+                // When connecting diagonals the rules must check the corners
+                // cut by the step, according to the diagonal policy.
+                //
+                // xb.
+                // a..
+                // ...
+                //
+                // In the above example, with the default policy, a cannot
+                // traverse from a to b because it would cut the corner `x`.
+                //
+                // A knight move crosses the two tiles between `from` and `to`
+                // along its longer side, and they are checked in the same way.
+                let (x, y) = from;
+                let (p, q) = to;
+                let (side_a, side_b) = if x.abs_diff(p) == 2 {
+                    ((x.midpoint(p), y), (x.midpoint(p), q))
+                } else if y.abs_diff(q) == 2 {
+                    ((x, y.midpoint(q)), (p, y.midpoint(q)))
+                } else {
+                    ((x, q), (p, y))
+                };
+                self.rules.can_move_diagonally(
+                    self.diagonal_policy,
+                    from_tile,
+                    to_tile,
+                    self.tile(side_a),
+                    self.tile(side_b),
+                )
+            }
+        }
+    }
+
+    /// The accessible tiles among the ones at `offsets` from `tile`.
+    fn neighbors_at(&self, tile: Coords2D, offsets: &[(isize, isize)]) -> ArrayVec<Coords2D, 8> {
+        if self.is_out_of_bound(tile) {
+            return ArrayVec::new();
+        }
+        let (x, y) = tile;
+        offsets
+            .iter()
+            .filter_map(|&(dx, dy)| {
+                let nx = x.checked_add_signed(dx)?;
+                let ny = y.checked_add_signed(dy)?;
+                Some((nx, ny))
+            })
+            .filter(|&neighbor| !self.is_out_of_bound(neighbor) && self.can_step(tile, neighbor))
+            .collect()
+    }
 
     fn coordinates_connect(&self, coords_a: Coords2D, coords_b: Coords2D) -> bool {
         let (x1, y1) = (coords_a.0 as isize, coords_a.1 as isize);
@@ -217,6 +1060,11 @@ impl MovingAiMap {
                 (y2 == y1 && (x2 == x1 + 1 || x2 == x1 - 1))
                     || (x2 == x1 && (y2 == y1 + 1 || y2 == y1 - 1))
             }
+            MapType::Hex => self.map_type.offsets().contains(&(x2 - x1, y2 - y1)),
+            MapType::SixteenConnected => {
+                let offset = (x2 - x1, y2 - y1);
+                self.map_type.offsets().contains(&offset) || KNIGHT_OFFSETS.contains(&offset)
+            }
         }
     }
 }
@@ -257,7 +1105,7 @@ impl Iterator for CoordsIter {
     }
 }
 
-impl Map2D<char> for MovingAiMap {
+impl<R: TraversalRules> Map2D<char> for MovingAiMap<R> {
     fn height(&self) -> usize {
         self.height
     }
@@ -267,7 +1115,11 @@ impl Map2D<char> for MovingAiMap {
     }
 
     fn get(&self, coords: Coords2D) -> &char {
-        &self.map[coords.1 * self.width() + coords.0]
+        &TILE_CHARS[self.tile_byte(coords) as usize]
+    }
+
+    fn get_copied(&self, coords: Coords2D) -> char {
+        self.tile_byte(coords) as char
     }
 
     fn is_out_of_bound(&self, coords: Coords2D) -> bool {
@@ -278,12 +1130,7 @@ impl Map2D<char> for MovingAiMap {
         if self.is_out_of_bound(tile) {
             return false;
         }
-        let tile_char = self.get(tile);
-        match *tile_char {
-            '.' | 'G' | 'S' | 'W' => true,
-            '@' | 'O' | 'T' => false,
-            _ => false, // Not recognized char.
-        }
+        self.rules.is_passable(self.tile(tile))
     }
 
     fn is_traversable_from(&self, from: Coords2D, to: Coords2D) -> bool {
@@ -296,45 +1143,7 @@ impl Map2D<char> for MovingAiMap {
         if !self.coordinates_connect(to, from) {
             return false;
         }
-        let diagonal = from.0 != to.0 && from.1 != to.1;
-        let tile_char = *(self.get(to));
-        let from_char = *(self.get(from));
-        match (self.map_type, diagonal) {
-            (MapType::FourConnected, _) | (MapType::Octile, false) => {
-                match (tile_char, from_char) {
-                    ('.', _) => true,
-                    ('G', _) => true,
-                    ('@', _) => false,
-                    ('O', _) => false,
-                    ('T', _) => false,
-                    ('S', '.') => true,
-                    ('S', 'S') => true,
-                    ('W', 'W') => true,
-                    _ => false,
-                }
-            }
-            (MapType::Octile, true) => {
-                // When connecting diagonals we need to check that the step is
-                // not cutting corner.
-                //
-                // xb.
-                // a..
-                // ...
-                //
-                // In the above example a cannot traverse from a to b because it
-                // would cut the corner `x`.
-                let (x, y) = from;
-                let (p, q) = to;
-                let intermediate_a = (x, q);
-                let intermediate_b = (p, y);
-                // A corner is not cut only if it is possible to reach the diagonal
-                // With a ANY double-step in a non-diagonal path.
-                self.is_traversable_from(from, intermediate_a)
-                    && self.is_traversable_from(intermediate_a, to)
-                    && self.is_traversable_from(from, intermediate_b)
-                    && self.is_traversable_from(intermediate_b, to)
-            }
-        }
+        self.can_step(from, to)
     }
 
     fn coords(&self) -> CoordsIter {
@@ -351,30 +1160,33 @@ impl Map2D<char> for MovingAiMap {
     }
 
     fn neighbors(&self, tile: Coords2D) -> ArrayVec<Coords2D, 8> {
-        const OFFSETS: [(isize, isize); 8] = [
-            (1, 0),
-            (-1, 0),
-            (0, 1),
-            (0, -1),
-            (1, 1),
-            (1, -1),
-            (-1, 1),
-            (-1, -1),
-        ];
-        let (x, y) = tile;
-        OFFSETS
-            .iter()
-            .filter_map(|&(dx, dy)| {
-                let nx = x.checked_add_signed(dx)?;
-                let ny = y.checked_add_signed(dy)?;
-                Some((nx, ny))
-            })
-            .filter(|&neighbor| self.is_traversable_from(tile, neighbor))
-            .collect()
+        self.neighbors_at(tile, self.map_type.offsets())
+    }
+
+    fn knight_neighbors(&self, tile: Coords2D) -> ArrayVec<Coords2D, 8> {
+        match self.map_type {
+            MapType::SixteenConnected => self.neighbors_at(tile, &KNIGHT_OFFSETS),
+            _ => ArrayVec::new(),
+        }
+    }
+
+    fn step_cost(&self, from: Coords2D, to: Coords2D) -> f64 {
+        match self.map_type {
+            MapType::Hex => 1.0,
+            _ => {
+                let dx = from.0.abs_diff(to.0) as f64;
+                let dy = from.1.abs_diff(to.1) as f64;
+                dx.hypot(dy)
+            }
+        }
+    }
+
+    fn heuristic(&self, from: Coords2D, to: Coords2D) -> f64 {
+        heuristics::for_map_type(self.map_type)(from, to)
     }
 }
 
-impl Index<Coords2D> for MovingAiMap {
+impl<R: TraversalRules> Index<Coords2D> for MovingAiMap<R> {
     type Output = char;
 
     fn index(&self, coords: Coords2D) -> &char {
@@ -382,6 +1194,28 @@ impl Index<Coords2D> for MovingAiMap {
     }
 }
 
+/// Serialize the one-byte tiles as characters, as the map was stored before
+/// the compact representation.
+#[cfg(feature = "serde")]
+mod tiles_serde {
+    use serde::{de::Error, Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(map: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(map.iter().map(|&tile| tile as char))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Box<[u8]>, D::Error> {
+        Vec::<char>::deserialize(deserializer)?
+            .into_iter()
+            .map(|tile| {
+                super::tile_to_byte(tile).ok_or_else(|| {
+                    D::Error::custom(format!("tile `{:?}` cannot be stored in a map", tile))
+                })
+            })
+            .collect()
+    }
+}
+
 /// Represent a row (scene) in a scene file.
 #[derive(Debug, PartialEq, Clone)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
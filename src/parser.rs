@@ -1,12 +1,19 @@
 #![allow(clippy::tabs_in_doc_comments)]
+use crate::map2d::MapType;
 use crate::map2d::MovingAiMap;
+use crate::map2d::ParseError;
 use crate::map2d::SceneRecord;
+use crate::map2d::tile_to_byte;
+use crate::tile::Tile;
 
 /// Contains all the parser functions.
 use std::fs::File;
 use std::io;
 use std::io::prelude::*;
+use std::io::BufReader;
+use std::io::BufWriter;
 use std::path;
+use std::str::FromStr;
 
 /// Parse a MovingAI `.map` file.
 ///
@@ -16,12 +23,9 @@ use std::path;
 /// # Returns
 ///  It returns the parsed map as a `MovingAiMap` or an `Err`.
 ///
-/// # Panics
-///  For the time, it panics if the map format it is not correct.
-///  TODO: Catch all these errors and encode them into `Result`.
-///
 /// # Errors
-///  Return errors if it is not possible to open the specified file.
+///  Return errors if it is not possible to open the specified file or if
+///  the file is not a valid `.map` (see `parse_map`).
 ///
 /// # Examples
 ///
@@ -31,95 +35,417 @@ use std::path;
 ///
 /// let map = parse_map_file(Path::new("./tests/arena.map")).unwrap();
 /// ```
-pub fn parse_map_file(path: &path::Path) -> io::Result<MovingAiMap> {
-    let mut file = File::open(path)?;
-    let mut contents = String::new();
-    file.read_to_string(&mut contents)?;
-
-    parse_map(&contents)
+pub fn parse_map_file(path: &path::Path) -> Result<MovingAiMap, ParseError> {
+    let file = File::open(path)?;
+    parse_map_reader(BufReader::new(file))
 }
 
 /// Parse a string representing a MovingAI `.map`.
 ///
+/// This is the same as calling `parse_map_with_options` with the default
+/// (lenient) options and discarding the warnings.
+///
 /// # Arguments
 ///  * `contents` a string in the `.map` format.
 ///
 /// # Returns
 ///  It returns the parsed map as a `MovingAiMap` or an `Err`.
 ///
-/// # Panics
-///  For the time, it panics if the map format it is not correct.
-///  TODO: Catch all these errors and encode them into `Result`.
-///
 /// # Errors
-///  Return errors if it is not possible to open the specified file.
+///  Return a `ParseError` pointing at the offending line and column if the
+///  `height`, `width` or `map` headers are missing, if a number is not valid,
+///  if a row is shorter than `width` or if there are less than `height` rows.
 ///
 /// # Examples
 ///
 /// ```
-/// use std::path::Path;
 /// use movingai::parser::parse_map;
 ///
 /// let map = parse_map("type octile\nheight 1\nwidth 1\nmap\nT").unwrap();
 /// ```
-pub fn parse_map(contents: &str) -> io::Result<MovingAiMap> {
-    let mut height: usize = 0;
-    let mut width: usize = 0;
-    let mut map_type = String::from("empty");
-    let mut map: Vec<char> = Vec::new();
+pub fn parse_map(contents: &str) -> Result<MovingAiMap, ParseError> {
+    parse_map_with_options(contents, &ParseOptions::default()).map(|(map, _)| map)
+}
 
-    let mut parse_map = false;
-    for line in contents.lines() {
-        if parse_map {
-            map.extend(line.chars());
-            continue;
+/// How the parser handles the problems that do not prevent building a map.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ParseMode {
+    /// Every problem is an error.
+    Strict,
+    /// Recoverable problems are collected as warnings and the parser moves on.
+    #[default]
+    Lenient,
+}
+
+/// Options for `parse_map_with_options`.
+///
+/// The recoverable problems are: unknown header keys, a missing or unknown
+/// map `type`, unknown tiles, rows longer than `width` and non-empty lines
+/// after the last row of the map. In `ParseMode::Lenient`, unknown types fall
+/// back to a 4-connected map, long rows are truncated and trailing lines are
+/// ignored.
+#[derive(Debug, Clone)]
+pub struct ParseOptions {
+    /// How recoverable problems are handled.
+    pub mode: ParseMode,
+    /// Accept `\r\n` line endings. When `false`, the `\r` is considered part
+    /// of the line. Default: `true`.
+    pub accept_crlf: bool,
+    /// Remove spaces and tabs at the end of every map row before checking
+    /// its length. Default: `false`.
+    pub trim_trailing_whitespace: bool,
+}
+
+impl Default for ParseOptions {
+    fn default() -> Self {
+        ParseOptions {
+            mode: ParseMode::default(),
+            accept_crlf: true,
+            trim_trailing_whitespace: false,
+        }
+    }
+}
+
+impl ParseOptions {
+    /// Options rejecting every problem in the input.
+    pub fn strict() -> ParseOptions {
+        ParseOptions {
+            mode: ParseMode::Strict,
+            ..ParseOptions::default()
         }
+    }
+
+    /// Options collecting recoverable problems as warnings.
+    pub fn lenient() -> ParseOptions {
+        ParseOptions {
+            mode: ParseMode::Lenient,
+            ..ParseOptions::default()
+        }
+    }
+}
+
+/// Parse a string representing a MovingAI `.map` with the given options.
+///
+/// # Arguments
+///  * `contents` a string in the `.map` format.
+///  * `options` how strictly the input is checked.
+///
+/// # Returns
+///  It returns the parsed map together with the warnings collected in
+///  `ParseMode::Lenient` (always empty in `ParseMode::Strict`).
+///
+/// # Errors
+///  Return a `ParseError` pointing at the offending line and column for the
+///  same problems as `parse_map`. In `ParseMode::Strict` the recoverable
+///  problems listed in `ParseOptions` are errors too.
+///
+/// # Examples
+///
+/// ```
+/// use movingai::parser::{parse_map_with_options, ParseOptions};
+///
+/// let contents = "type octile\nheight 1\nwidth 2\nmap\n.X";
+/// let (map, warnings) = parse_map_with_options(contents, &ParseOptions::lenient()).unwrap();
+/// assert_eq!(warnings.len(), 1);
+/// assert!(parse_map_with_options(contents, &ParseOptions::strict()).is_err());
+/// ```
+pub fn parse_map_with_options(
+    contents: &str,
+    options: &ParseOptions,
+) -> Result<(MovingAiMap, Vec<ParseError>), ParseError> {
+    parse_map_reader_with_options(contents.as_bytes(), options)
+}
+
+/// Parse a MovingAI `.map` from a buffered reader.
+///
+/// The input is read one line at a time, so this works on any source (e.g.,
+/// `stdin`) without loading it all into memory first. It uses the default
+/// (lenient) options, just like `parse_map`.
+///
+/// # Arguments
+///  * `reader` the source of the `.map` content.
+///
+/// # Errors
+///  Return `ParseError::Io` if reading fails (including when the input is
+///  not valid UTF-8) and the same errors as `parse_map` otherwise.
+///
+/// # Examples
+///
+/// ```
+/// use std::fs::File;
+/// use std::io::BufReader;
+/// use movingai::parser::parse_map_reader;
+///
+/// let file = File::open("./tests/arena.map").unwrap();
+/// let map = parse_map_reader(BufReader::new(file)).unwrap();
+/// ```
+pub fn parse_map_reader<R: BufRead>(reader: R) -> Result<MovingAiMap, ParseError> {
+    parse_map_reader_with_options(reader, &ParseOptions::default()).map(|(map, _)| map)
+}
+
+/// Parse a MovingAI `.map` from a buffered reader with the given options.
+///
+/// See `parse_map_with_options` and `parse_map_reader`.
+///
+/// # Errors
+///  Return `ParseError::Io` if reading fails and the same errors as
+///  `parse_map_with_options` otherwise.
+pub fn parse_map_reader_with_options<R: BufRead>(
+    reader: R,
+    options: &ParseOptions,
+) -> Result<(MovingAiMap, Vec<ParseError>), ParseError> {
+    let mut warnings = Warnings::new(options.mode);
+    let mut height: Option<usize> = None;
+    let mut width: Option<usize> = None;
+    let mut map_type: Option<String> = None;
+
+    let mut lines = LineReader::new(reader, options.accept_crlf);
+    let mut body_line = None;
+    let mut last_line = 0;
+    while let Some((line_no, line)) = lines.next_line()? {
+        last_line = line_no;
         if line.trim() == "map" {
-            parse_map = true;
-        } else {
-            let mut parts = line.split_whitespace();
-            if let (Some(key), Some(value)) = (parts.next(), parts.next()) {
-                if parts.next().is_none() {
-                    match key {
-                        "type" => map_type = value.to_string(),
-                        "height" => {
-                            height = value.parse::<usize>().map_err(|_| {
-                                io::Error::new(
-                                    io::ErrorKind::InvalidData,
-                                    "Error parsing map height.",
-                                )
-                            })?
-                        }
-                        "width" => {
-                            width = value.parse::<usize>().map_err(|_| {
-                                io::Error::new(
-                                    io::ErrorKind::InvalidData,
-                                    "Error parsing map width.",
-                                )
-                            })?
-                        }
-                        _ => {}
+            body_line = Some(line_no);
+            break;
+        }
+        let mut parts = tokens(line);
+        match (parts.next(), parts.next(), parts.next()) {
+            (None, _, _) => {}
+            (Some((_, key)), Some((column, value)), None) => match key {
+                "type" => {
+                    if !MapType::is_known(value) {
+                        warnings.report(ParseError::UnknownMapType {
+                            line: line_no,
+                            column,
+                            text: value.to_string(),
+                        })?;
                     }
+                    map_type = Some(value.to_string());
                 }
+                "height" => height = Some(parse_size(value, "height", line_no, column)?),
+                "width" => width = Some(parse_size(value, "width", line_no, column)?),
+                _ => warnings.report(ParseError::UnknownHeader {
+                    line: line_no,
+                    column: 1,
+                    text: line.to_string(),
+                })?,
+            },
+            (Some((column, _)), _, _) => warnings.report(ParseError::UnknownHeader {
+                line: line_no,
+                column,
+                text: line.to_string(),
+            })?,
+        }
+    }
+
+    let body_line = body_line.ok_or(ParseError::MissingHeader {
+        key: "map",
+        line: last_line + 1,
+        column: 1,
+    })?;
+    let height = height.ok_or(ParseError::MissingHeader {
+        key: "height",
+        line: body_line,
+        column: 1,
+    })?;
+    let width = width.ok_or(ParseError::MissingHeader {
+        key: "width",
+        line: body_line,
+        column: 1,
+    })?;
+    let map_type = match map_type {
+        Some(map_type) => map_type,
+        None => {
+            warnings.report(ParseError::MissingHeader {
+                key: "type",
+                line: body_line,
+                column: 1,
+            })?;
+            String::from("empty")
+        }
+    };
+
+    if height.checked_mul(width).is_none() {
+        return Err(ParseError::InvalidMapSize);
+    }
+    // The header cannot be trusted: the map grows with the rows actually read.
+    let mut map: Vec<char> = Vec::new();
+    let mut rows = 0;
+    while let Some((line_no, line)) = lines.next_line()? {
+        last_line = line_no;
+        if rows >= height {
+            if !line.trim().is_empty() {
+                warnings.report(ParseError::TrailingData {
+                    line: line_no,
+                    column: 1,
+                    text: line.to_string(),
+                })?;
+            }
+            continue;
+        }
+        let row = if options.trim_trailing_whitespace {
+            line.trim_end_matches([' ', '\t'])
+        } else {
+            line
+        };
+        let found = row.chars().count();
+        if found < width {
+            return Err(ParseError::ShortRow {
+                row: rows,
+                line: line_no,
+                column: found + 1,
+                expected: width,
+                found,
+                text: line.to_string(),
+            });
+        }
+        if found > width {
+            warnings.report(ParseError::LongRow {
+                row: rows,
+                line: line_no,
+                column: width + 1,
+                expected: width,
+                found,
+                text: line.to_string(),
+            })?;
+        }
+        for (i, tile) in row.chars().take(width).enumerate() {
+            if tile_to_byte(tile).is_none() {
+                // This cannot be stored in the map, not even in lenient mode.
+                return Err(ParseError::UnsupportedTile {
+                    coords: (i, rows),
+                    tile,
+                    line: Some(line_no),
+                    column: Some(i + 1),
+                });
             }
+            if !Tile::from(tile).is_known() {
+                warnings.report(ParseError::UnknownTile {
+                    line: line_no,
+                    column: i + 1,
+                    tile,
+                })?;
+            }
+            map.push(tile);
+        }
+        rows += 1;
+    }
+    if rows < height {
+        return Err(ParseError::MissingRows {
+            line: last_line + 1,
+            column: 1,
+            expected: height,
+            found: rows,
+        });
+    }
+
+    let map = MovingAiMap::new(map_type, height, width, map)?;
+    Ok((map, warnings.into_inner()))
+}
+
+/// Read a `BufRead` one line at a time, reusing the same buffer.
+struct LineReader<R> {
+    reader: R,
+    buffer: String,
+    line_no: usize,
+    accept_crlf: bool,
+}
+
+impl<R: BufRead> LineReader<R> {
+    fn new(reader: R, accept_crlf: bool) -> LineReader<R> {
+        LineReader {
+            reader,
+            buffer: String::new(),
+            line_no: 0,
+            accept_crlf,
         }
     }
 
-    if height == 0 {
-        return Err(io::Error::new(
-            io::ErrorKind::InvalidData,
-            "Map height is missing or zero.",
-        ));
+    /// Return the next line (without the line ending) and its 1-based number.
+    fn next_line(&mut self) -> Result<Option<(usize, &str)>, ParseError> {
+        self.buffer.clear();
+        if self.reader.read_line(&mut self.buffer)? == 0 {
+            return Ok(None);
+        }
+        self.line_no += 1;
+        let line = self.buffer.strip_suffix('\n').unwrap_or(&self.buffer);
+        let line = if self.accept_crlf {
+            line.strip_suffix('\r').unwrap_or(line)
+        } else {
+            line
+        };
+        Ok(Some((self.line_no, line)))
+    }
+}
+
+/// Collect the recoverable problems according to a `ParseMode`.
+struct Warnings {
+    mode: ParseMode,
+    warnings: Vec<ParseError>,
+}
+
+impl Warnings {
+    fn new(mode: ParseMode) -> Warnings {
+        Warnings {
+            mode,
+            warnings: Vec::new(),
+        }
+    }
+
+    /// Fail on `problem` in strict mode, record it otherwise.
+    fn report(&mut self, problem: ParseError) -> Result<(), ParseError> {
+        match self.mode {
+            ParseMode::Strict => Err(problem),
+            ParseMode::Lenient => {
+                self.warnings.push(problem);
+                Ok(())
+            }
+        }
     }
-    if width == 0 {
-        return Err(io::Error::new(
-            io::ErrorKind::InvalidData,
-            "Map width is missing or zero.",
-        ));
+
+    fn into_inner(self) -> Vec<ParseError> {
+        self.warnings
     }
+}
 
-    MovingAiMap::new(map_type, height, width, map)
-        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e.to_string()))
+/// Write a `MovingAiMap` into a MovingAI `.map` file.
+///
+/// # Arguments
+///  * `path` represents the path to the file location.
+///  * `map` the map to write.
+///
+/// # Errors
+///  Return errors if it is not possible to create or write the specified file.
+pub fn write_map_file(path: &path::Path, map: &MovingAiMap) -> io::Result<()> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    map.write_to(&mut writer)?;
+    writer.flush()
+}
+
+/// Write a `MovingAiMap` into a string in the MovingAI `.map` format.
+///
+/// The result can be parsed back with `parse_map` into a map with the same
+/// type, size and tiles. The diagonal policy and the traversal rules are not
+/// part of the format: the parsed map has the default ones.
+///
+/// # Arguments
+///  * `map` the map to write.
+///
+/// # Examples
+///
+/// ```
+/// use movingai::parser::{parse_map, write_map};
+///
+/// let map = parse_map("type octile\nheight 1\nwidth 2\nmap\nT.").unwrap();
+/// let contents = write_map(&map);
+/// assert_eq!(contents, "type octile\nheight 1\nwidth 2\nmap\nT.\n");
+/// assert_eq!(parse_map(&contents).unwrap(), map);
+/// ```
+pub fn write_map(map: &MovingAiMap) -> String {
+    let mut contents = Vec::new();
+    map.write_to(&mut contents)
+        .expect("writing into a Vec cannot fail");
+    String::from_utf8(contents).expect("maps are written as valid UTF-8")
 }
 
 /// Parse a MovingAI `.scen` file.
@@ -130,11 +456,9 @@ pub fn parse_map(contents: &str) -> io::Result<MovingAiMap> {
 /// # Returns
 ///  It returns the parsed map as a `Vec<SceneRecord>` or an `Err`.
 ///
-/// # Panics
-///  For the time, it panics if the map format it is not correct.
-///
 /// # Errors
-///  Return errors if it is not possible to open the specified file.
+///  Return errors if it is not possible to open the specified file or if
+///  the file is not a valid `.scen` (see `parse_scen`).
 ///
 /// # Examples
 ///
@@ -144,12 +468,9 @@ pub fn parse_map(contents: &str) -> io::Result<MovingAiMap> {
 ///
 /// let scen = parse_scen_file(Path::new("./tests/arena2.map.scen")).unwrap();
 /// ```
-pub fn parse_scen_file(path: &path::Path) -> io::Result<Vec<SceneRecord>> {
-    let mut file = File::open(path)?;
-    let mut contents = String::new();
-    file.read_to_string(&mut contents)?;
-
-    parse_scen(&contents)
+pub fn parse_scen_file(path: &path::Path) -> Result<Vec<SceneRecord>, ParseError> {
+    let file = File::open(path)?;
+    ScenReader::new(BufReader::new(file)).collect()
 }
 
 /// Parse a string representing a MovingAI `.scen`.
@@ -160,69 +481,248 @@ pub fn parse_scen_file(path: &path::Path) -> io::Result<Vec<SceneRecord>> {
 /// # Returns
 ///  It returns the parsed map as a `Vec<SceneRecord>` or an `Err`.
 ///
-/// # Panics
-///  For the time, it panics if the map format it is not correct.
-///
 /// # Errors
-///  Return errors if it is not possible to open the specified file.
+///  Return a `ParseError` pointing at the offending line and column if a
+///  record has less than 9 fields or if a numeric field is not valid.
 ///
 /// # Examples
 ///
 /// ```
-/// use std::path::Path;
 /// use movingai::parser::parse_scen;
 ///
 /// let scen = parse_scen("version 1\n0	maps/dao/arena.map	49	49	1	11	1	12	1").unwrap();
 /// ```
-pub fn parse_scen(contents: &str) -> io::Result<Vec<SceneRecord>> {
-    let mut table: Vec<SceneRecord> = Vec::new();
+pub fn parse_scen(contents: &str) -> Result<Vec<SceneRecord>, ParseError> {
+    ScenReader::new(contents.as_bytes()).collect()
+}
 
-    for line in contents.lines() {
-        if line.starts_with("version") {
-            continue;
-        }
-        if line.is_empty() {
-            continue;
+/// An iterator parsing a MovingAI `.scen` one record at a time.
+///
+/// Only the current line is kept in memory, so it can process arbitrarily
+/// large scenario files (or concatenations of them) and pipes such as
+/// `stdin`. The `version` headers and the empty lines are skipped. After an
+/// error, the iterator moves on to the next line.
+///
+/// # Examples
+///
+/// ```
+/// use std::fs::File;
+/// use std::io::BufReader;
+/// use movingai::parser::ScenReader;
+///
+/// let file = File::open("./tests/arena2.map.scen").unwrap();
+/// for record in ScenReader::new(BufReader::new(file)) {
+///     let record = record.unwrap();
+///     assert_eq!(record.map_file, "maps/dao/arena2.map");
+/// }
+/// ```
+pub struct ScenReader<R> {
+    lines: LineReader<R>,
+}
+
+impl<R: BufRead> ScenReader<R> {
+    /// Create a new `ScenReader` reading from `reader`.
+    pub fn new(reader: R) -> ScenReader<R> {
+        ScenReader {
+            lines: LineReader::new(reader, true),
         }
-        let record: Vec<&str> = line.split('\t').collect();
-        if record.len() < 9 {
-            return Err(io::Error::new(
-                io::ErrorKind::InvalidData,
-                format!("Expected 9 fields in scene record, found {}", record.len()),
-            ));
+    }
+}
+
+impl<R: BufRead> Iterator for ScenReader<R> {
+    type Item = Result<SceneRecord, ParseError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            match self.lines.next_line() {
+                Err(e) => return Some(Err(e)),
+                Ok(None) => return None,
+                Ok(Some((line_no, line))) => {
+                    if line.starts_with("version") || line.is_empty() {
+                        continue;
+                    }
+                    return Some(parse_scen_line(line, line_no));
+                }
+            }
         }
-        table.push(SceneRecord {
-            bucket: record[0].parse::<u32>().map_err(|_| {
-                io::Error::new(io::ErrorKind::InvalidData, "Error parsing bucket size.")
-            })?,
-            map_file: String::from(record[1]),
-            map_width: record[2].parse::<usize>().map_err(|_| {
-                io::Error::new(io::ErrorKind::InvalidData, "Error parsing map width.")
-            })?,
-            map_height: record[3].parse::<usize>().map_err(|_| {
-                io::Error::new(io::ErrorKind::InvalidData, "Error parsing map height.")
-            })?,
-            start_pos: (
-                record[4].parse::<usize>().map_err(|_| {
-                    io::Error::new(io::ErrorKind::InvalidData, "Error parsing start x.")
-                })?,
-                record[5].parse::<usize>().map_err(|_| {
-                    io::Error::new(io::ErrorKind::InvalidData, "Error parsing start y.")
-                })?,
-            ),
-            goal_pos: (
-                record[6].parse::<usize>().map_err(|_| {
-                    io::Error::new(io::ErrorKind::InvalidData, "Error parsing goal x")
-                })?,
-                record[7].parse::<usize>().map_err(|_| {
-                    io::Error::new(io::ErrorKind::InvalidData, "Error parsing goal y")
-                })?,
-            ),
-            optimal_length: record[8].parse::<f64>().map_err(|_| {
-                io::Error::new(io::ErrorKind::InvalidData, "Error parsing optimal length.")
-            })?,
-        })
     }
+}
+
+/// Parse a single record of a `.scen` file.
+///
+/// Trailing whitespace (e.g., a tab after the optimal length) is ignored.
+fn parse_scen_line(line: &str, line_no: usize) -> Result<SceneRecord, ParseError> {
+    let line = line.trim_end();
+    let record: Vec<(usize, &str)> = line
+        .split('\t')
+        .map(|field| (column_of(line, field), field))
+        .collect();
+    if record.len() != 9 {
+        return Err(ParseError::WrongFieldCount {
+            line: line_no,
+            column: match record.get(9) {
+                Some(&(column, _)) => column,
+                None => line.chars().count() + 1,
+            },
+            expected: 9,
+            found: record.len(),
+            text: line.to_string(),
+        });
+    }
+    Ok(SceneRecord {
+        bucket: parse_field(&record, 0, "bucket", line_no)?,
+        map_file: String::from(record[1].1),
+        map_width: parse_field(&record, 2, "map width", line_no)?,
+        map_height: parse_field(&record, 3, "map height", line_no)?,
+        start_pos: (
+            parse_field(&record, 4, "start x", line_no)?,
+            parse_field(&record, 5, "start y", line_no)?,
+        ),
+        goal_pos: (
+            parse_field(&record, 6, "goal x", line_no)?,
+            parse_field(&record, 7, "goal y", line_no)?,
+        ),
+        optimal_length: parse_field(&record, 8, "optimal length", line_no)?,
+    })
+}
+
+/// The number of decimals used for `optimal_length` in the official
+/// MovingAI `.scen` files.
+pub const SCEN_PRECISION: usize = 8;
+
+/// Write a list of `SceneRecord` into a MovingAI `.scen` file.
+///
+/// # Arguments
+///  * `path` represents the path to the file location.
+///  * `records` the scenes to write.
+///  * `precision` the number of decimals used for `optimal_length`.
+///
+/// # Errors
+///  Return errors if it is not possible to create or write the specified file.
+pub fn write_scen_file(
+    path: &path::Path,
+    records: &[SceneRecord],
+    precision: usize,
+) -> io::Result<()> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    write_scen_to(&mut writer, records, precision)?;
+    writer.flush()
+}
+
+/// Write a list of `SceneRecord` in the MovingAI `.scen` format.
+///
+/// The output starts with the `version 1` header and contains one
+/// tab-separated line for every record.
+///
+/// # Arguments
+///  * `writer` where to write the scenes.
+///  * `records` the scenes to write.
+///  * `precision` the number of decimals used for `optimal_length`.
+///
+/// # Errors
+///  Return errors if it is not possible to write on `writer`.
+pub fn write_scen_to<W: Write>(
+    mut writer: W,
+    records: &[SceneRecord],
+    precision: usize,
+) -> io::Result<()> {
+    writeln!(writer, "version 1")?;
+    for record in records {
+        writeln!(
+            writer,
+            "{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{:.*}",
+            record.bucket,
+            record.map_file,
+            record.map_width,
+            record.map_height,
+            record.start_pos.0,
+            record.start_pos.1,
+            record.goal_pos.0,
+            record.goal_pos.1,
+            precision,
+            record.optimal_length
+        )?;
+    }
+    Ok(())
+}
 
-    Ok(table)
+/// Write a list of `SceneRecord` into a string in the MovingAI `.scen` format.
+///
+/// # Arguments
+///  * `records` the scenes to write.
+///  * `precision` the number of decimals used for `optimal_length`. Use
+///    `SCEN_PRECISION` to match the official benchmark files.
+///
+/// # Examples
+///
+/// ```
+/// use movingai::parser::{parse_scen, write_scen, SCEN_PRECISION};
+///
+/// let scen = parse_scen("version 1\n0\tmaps/dao/arena.map\t49\t49\t1\t11\t2\t12\t1.41421356").unwrap();
+/// assert_eq!(
+///     write_scen(&scen, SCEN_PRECISION),
+///     "version 1\n0\tmaps/dao/arena.map\t49\t49\t1\t11\t2\t12\t1.41421356\n"
+/// );
+/// ```
+pub fn write_scen(records: &[SceneRecord], precision: usize) -> String {
+    let mut contents = Vec::new();
+    write_scen_to(&mut contents, records, precision).expect("writing into a Vec cannot fail");
+    String::from_utf8(contents).expect("scenes are written as valid UTF-8")
+}
+
+/// Split a line on whitespace, pairing every token with its 1-based column.
+fn tokens(line: &str) -> impl Iterator<Item = (usize, &str)> {
+    line.split_whitespace()
+        .map(move |token| (column_of(line, token), token))
+}
+
+/// Return the 1-based column at which `field` starts.
+///
+/// `field` must be a sub-slice of `line`.
+fn column_of(line: &str, field: &str) -> usize {
+    let offset = field.as_ptr() as usize - line.as_ptr() as usize;
+    line[..offset].chars().count() + 1
+}
+
+fn parse_number<T: FromStr>(
+    text: &str,
+    field: &'static str,
+    line: usize,
+    column: usize,
+) -> Result<T, ParseError> {
+    text.parse::<T>().map_err(|_| ParseError::InvalidNumber {
+        field,
+        line,
+        column,
+        text: text.to_string(),
+    })
+}
+
+/// Parse the `index`-th field of a scene record.
+fn parse_field<T: FromStr>(
+    record: &[(usize, &str)],
+    index: usize,
+    field: &'static str,
+    line: usize,
+) -> Result<T, ParseError> {
+    let (column, text) = record[index];
+    parse_number(text, field, line, column)
+}
+
+/// Parse a map dimension. A dimension must be a positive integer.
+fn parse_size(
+    text: &str,
+    field: &'static str,
+    line: usize,
+    column: usize,
+) -> Result<usize, ParseError> {
+    match parse_number(text, field, line, column)? {
+        0 => Err(ParseError::InvalidNumber {
+            field,
+            line,
+            column,
+            text: text.to_string(),
+        }),
+        size => Ok(size),
+    }
 }
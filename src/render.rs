@@ -0,0 +1,469 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::ops::Range;
+use std::path;
+
+use crate::map2d::{Coords2D, Map2D};
+
+/// The symbol of the start tile.
+pub const START: char = 's';
+/// The symbol of the goal tile.
+pub const GOAL: char = 'g';
+/// The symbol of the path tiles with `PathStyle::Stars`.
+pub const PATH: char = '*';
+/// The symbol of the expanded tiles.
+pub const EXPANDED: char = 'x';
+/// The symbol of the generated tiles.
+pub const GENERATED: char = '+';
+
+/// How the tiles of a path are drawn.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum PathStyle {
+    /// Every tile shows the direction of the next step: `>`, `<`, `^` and
+    /// `v` for straight steps, `/` and `\` for diagonal ones. The last tile
+    /// is drawn as `*`.
+    #[default]
+    Arrows,
+    /// Every tile is drawn as `*`.
+    Stars,
+}
+
+/// What to draw over the tiles of a map.
+///
+/// When a tile belongs to several layers, the start and goal are drawn over
+/// the path, the path over the expanded tiles and the expanded tiles over the
+/// generated ones. Tiles out of the map are ignored.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Overlay<'a> {
+    /// The tiles of a path, in order.
+    pub path: &'a [Coords2D],
+    /// How the path is drawn. Default: `PathStyle::Arrows`.
+    pub path_style: PathStyle,
+    /// The start tile.
+    pub start: Option<Coords2D>,
+    /// The goal tile.
+    pub goal: Option<Coords2D>,
+    /// The tiles expanded by a search.
+    pub expanded: &'a [Coords2D],
+    /// The tiles generated by a search.
+    pub generated: &'a [Coords2D],
+}
+
+impl<'a> Overlay<'a> {
+    /// An overlay drawing `path` with arrows, with its first and last tiles
+    /// as start and goal.
+    pub fn path(path: &'a [Coords2D]) -> Overlay<'a> {
+        Overlay {
+            path,
+            start: path.first().copied(),
+            goal: path.last().copied(),
+            ..Overlay::default()
+        }
+    }
+}
+
+/// A rectangle of tiles of a map.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Window {
+    /// The top-left tile.
+    pub origin: Coords2D,
+    /// The number of columns.
+    pub width: usize,
+    /// The number of rows.
+    pub height: usize,
+}
+
+impl Window {
+    /// The window of the tiles at most `radius` steps away from `center`
+    /// on both axes. It can extend beyond the map: rendering crops it.
+    pub fn around(center: Coords2D, radius: usize) -> Window {
+        let origin = (
+            center.0.saturating_sub(radius),
+            center.1.saturating_sub(radius),
+        );
+        Window {
+            origin,
+            width: center.0.saturating_add(radius).saturating_add(1) - origin.0,
+            height: center.1.saturating_add(radius).saturating_add(1) - origin.1,
+        }
+    }
+}
+
+/// Draw a map as text, one line per row, with `overlay` on top of it.
+///
+/// # Examples
+///
+/// ```
+/// use movingai::render::{render, Overlay};
+/// use movingai::MovingAiMap;
+///
+/// let map = MovingAiMap::new(String::from("octile"), 3, 3, vec!['.'; 9]).unwrap();
+/// let path = [(0, 0), (1, 0), (2, 0), (2, 1), (2, 2)];
+/// assert_eq!(render(&map, &Overlay::path(&path)), "s>v\n..v\n..g\n");
+/// ```
+pub fn render<M: Map2D<char>>(map: &M, overlay: &Overlay) -> String {
+    let window = Window {
+        origin: (0, 0),
+        width: map.width(),
+        height: map.height(),
+    };
+    render_window(map, window, overlay)
+}
+
+/// Draw the tiles of a map inside `window` as text, one line per row, with
+/// `overlay` on top of them.
+///
+/// The window is cropped to the map.
+///
+/// # Examples
+///
+/// ```
+/// use std::path::Path;
+/// use movingai::parser::parse_map_file;
+/// use movingai::render::{render_window, Overlay, Window};
+///
+/// let map = parse_map_file(Path::new("./tests/arena.map")).unwrap();
+/// let overlay = Overlay { start: Some((3, 1)), ..Overlay::default() };
+/// let window = Window::around((3, 1), 1);
+/// assert_eq!(render_window(&map, window, &overlay), "TTT\nTs.\n...\n");
+/// ```
+pub fn render_window<M: Map2D<char>>(map: &M, window: Window, overlay: &Overlay) -> String {
+    let (x0, y0) = window.origin;
+    let x1 = x0.saturating_add(window.width).min(map.width());
+    let y1 = y0.saturating_add(window.height).min(map.height());
+    if x0 >= x1 || y0 >= y1 {
+        return String::new();
+    }
+    let width = x1 - x0;
+    let mut symbols: Vec<char> = (y0..y1)
+        .flat_map(|y| (x0..x1).map(move |x| (x, y)))
+        .map(|coords| map.get_copied(coords))
+        .collect();
+    draw_overlay(&mut symbols, x0..x1, y0..y1, overlay, |mark| match mark {
+        Mark::Generated => GENERATED,
+        Mark::Expanded => EXPANDED,
+        Mark::Path(from, Some(next)) if overlay.path_style == PathStyle::Arrows => {
+            arrow(from, next)
+        }
+        Mark::Path(..) => PATH,
+        Mark::Start => START,
+        Mark::Goal => GOAL,
+    });
+
+    let mut result = String::with_capacity(symbols.len() + symbols.len() / width);
+    for row in symbols.chunks(width) {
+        result.extend(row);
+        result.push('\n');
+    }
+    result
+}
+
+/// A colour, as red, green and blue components.
+pub type Rgb = [u8; 3];
+
+/// The colour of every tile character in an image.
+///
+/// The default palette follows the MovingAI images: passable ground is
+/// white, out of bounds tiles are black, trees green, swamps olive and water
+/// blue.
+///
+/// # Examples
+///
+/// ```
+/// use movingai::render::Palette;
+///
+/// let palette = Palette::default().with('T', [34, 139, 34]);
+/// assert_eq!(palette.color('T'), [34, 139, 34]);
+/// assert_eq!(palette.color('.'), [255, 255, 255]);
+/// assert_eq!(palette.color('?'), palette.unknown());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Palette {
+    colors: HashMap<char, Rgb>,
+    unknown: Rgb,
+}
+
+impl Default for Palette {
+    fn default() -> Self {
+        Palette::new([128, 128, 128])
+            .with('.', [255, 255, 255])
+            .with('G', [255, 255, 255])
+            .with('@', [0, 0, 0])
+            .with('O', [0, 0, 0])
+            .with('T', [0, 128, 0])
+            .with('S', [128, 128, 0])
+            .with('W', [0, 0, 255])
+    }
+}
+
+impl Palette {
+    /// A palette drawing every tile with the `unknown` colour.
+    pub fn new(unknown: Rgb) -> Palette {
+        Palette {
+            colors: HashMap::new(),
+            unknown,
+        }
+    }
+
+    /// Set the colour of the tiles `tile`.
+    pub fn with(mut self, tile: char, color: Rgb) -> Palette {
+        self.colors.insert(tile, color);
+        self
+    }
+
+    /// The colour of the tiles `tile`.
+    pub fn color(&self, tile: char) -> Rgb {
+        self.colors.get(&tile).copied().unwrap_or(self.unknown)
+    }
+
+    /// The colour of the tiles without a colour of their own.
+    pub fn unknown(&self) -> Rgb {
+        self.unknown
+    }
+}
+
+/// Options for exporting a map as an image.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImageOptions {
+    /// The colours of the tiles.
+    pub palette: Palette,
+    /// The width and height of every tile in pixels. Default: `1`.
+    pub scale: usize,
+    /// The colour of the path of the overlay. Default: red.
+    pub path: Rgb,
+    /// The colour of the start tile. Default: cyan.
+    pub start: Rgb,
+    /// The colour of the goal tile. Default: magenta.
+    pub goal: Rgb,
+    /// The colour of the expanded tiles. Default: yellow.
+    pub expanded: Rgb,
+    /// The colour of the generated tiles. Default: light yellow.
+    pub generated: Rgb,
+}
+
+impl Default for ImageOptions {
+    fn default() -> Self {
+        ImageOptions {
+            palette: Palette::default(),
+            scale: 1,
+            path: [255, 0, 0],
+            start: [0, 255, 255],
+            goal: [255, 0, 255],
+            expanded: [255, 255, 0],
+            generated: [255, 255, 160],
+        }
+    }
+}
+
+/// Write a map with `overlay` as a binary PPM (`P6`) image.
+///
+/// Every tile is a square of `options.scale` pixels per side, coloured by
+/// `options.palette`. The overlay is drawn with the colours of `options`;
+/// path styles are ignored.
+///
+/// # Errors
+///  Return errors if `options.scale` is zero, if the size of the image does
+///  not fit in a `usize` or if it is not possible to write into `writer`.
+///
+/// # Examples
+///
+/// ```
+/// use movingai::render::{write_ppm, ImageOptions, Overlay};
+/// use movingai::MovingAiMap;
+///
+/// let map = MovingAiMap::new(String::from("octile"), 1, 2, vec!['.', 'T']).unwrap();
+/// let mut image = Vec::new();
+/// write_ppm(&map, &Overlay::default(), &ImageOptions::default(), &mut image).unwrap();
+/// assert_eq!(image, b"P6\n2 1\n255\n\xff\xff\xff\x00\x80\x00");
+/// ```
+pub fn write_ppm<M: Map2D<char>, W: Write>(
+    map: &M,
+    overlay: &Overlay,
+    options: &ImageOptions,
+    writer: W,
+) -> io::Result<()> {
+    write_image(map, overlay, options, writer, Channels::Rgb)
+}
+
+/// Write a map with `overlay` as a binary PGM (`P5`) image.
+///
+/// It is like `write_ppm`, but every colour is converted to its luma.
+///
+/// # Errors
+///  Return errors if `options.scale` is zero, if the size of the image does
+///  not fit in a `usize` or if it is not possible to write into `writer`.
+pub fn write_pgm<M: Map2D<char>, W: Write>(
+    map: &M,
+    overlay: &Overlay,
+    options: &ImageOptions,
+    writer: W,
+) -> io::Result<()> {
+    write_image(map, overlay, options, writer, Channels::Gray)
+}
+
+/// Write a map with `overlay` into a binary PPM file.
+///
+/// # Errors
+///  Return errors if `options.scale` is zero, if the size of the image does
+///  not fit in a `usize` or if it is not possible to create or write the
+///  specified file.
+pub fn write_ppm_file<M: Map2D<char>>(
+    path: &path::Path,
+    map: &M,
+    overlay: &Overlay,
+    options: &ImageOptions,
+) -> io::Result<()> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    write_ppm(map, overlay, options, &mut writer)?;
+    writer.flush()
+}
+
+/// Write a map with `overlay` into a binary PGM file.
+///
+/// # Errors
+///  Return errors if `options.scale` is zero, if the size of the image does
+///  not fit in a `usize` or if it is not possible to create or write the
+///  specified file.
+pub fn write_pgm_file<M: Map2D<char>>(
+    path: &path::Path,
+    map: &M,
+    overlay: &Overlay,
+    options: &ImageOptions,
+) -> io::Result<()> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    write_pgm(map, overlay, options, &mut writer)?;
+    writer.flush()
+}
+
+/// The pixel format of an image.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Channels {
+    Rgb,
+    Gray,
+}
+
+fn write_image<M: Map2D<char>, W: Write>(
+    map: &M,
+    overlay: &Overlay,
+    options: &ImageOptions,
+    mut writer: W,
+    channels: Channels,
+) -> io::Result<()> {
+    let scale = options.scale;
+    if scale == 0 {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "the scale of an image must be at least 1",
+        ));
+    }
+    let (width, height) = (map.width(), map.height());
+    let (Some(image_width), Some(image_height)) =
+        (width.checked_mul(scale), height.checked_mul(scale))
+    else {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "the scale of the image is too large for the map",
+        ));
+    };
+    let mut colors: Vec<Rgb> = map
+        .coords()
+        .map(|coords| options.palette.color(map.get_copied(coords)))
+        .collect();
+    draw_overlay(
+        &mut colors,
+        0..width,
+        0..height,
+        overlay,
+        |mark| match mark {
+            Mark::Generated => options.generated,
+            Mark::Expanded => options.expanded,
+            Mark::Path(..) => options.path,
+            Mark::Start => options.start,
+            Mark::Goal => options.goal,
+        },
+    );
+
+    let magic = match channels {
+        Channels::Rgb => "P6",
+        Channels::Gray => "P5",
+    };
+    write!(writer, "{}\n{} {}\n255\n", magic, image_width, image_height)?;
+    let mut line = Vec::new();
+    for row in colors.chunks(width.max(1)) {
+        line.clear();
+        for &[r, g, b] in row {
+            for _ in 0..scale {
+                match channels {
+                    Channels::Rgb => line.extend_from_slice(&[r, g, b]),
+                    Channels::Gray => line.push(luma([r, g, b])),
+                }
+            }
+        }
+        for _ in 0..scale {
+            writer.write_all(&line)?;
+        }
+    }
+    Ok(())
+}
+
+/// The luma of a colour (ITU-R BT.601).
+fn luma([r, g, b]: Rgb) -> u8 {
+    ((299 * r as u32 + 587 * g as u32 + 114 * b as u32 + 500) / 1000) as u8
+}
+
+/// A layer of an overlay.
+#[derive(Debug, Clone, Copy)]
+enum Mark {
+    Generated,
+    Expanded,
+    /// A tile of the path and the next one.
+    Path(Coords2D, Option<Coords2D>),
+    Start,
+    Goal,
+}
+
+/// Draw the layers of `overlay` over `cells`, the tiles in `xs` and `ys` in
+/// row-major order, converting them with `symbol`.
+fn draw_overlay<S>(
+    cells: &mut [S],
+    xs: Range<usize>,
+    ys: Range<usize>,
+    overlay: &Overlay,
+    symbol: impl Fn(Mark) -> S,
+) {
+    let mut draw = |(x, y): Coords2D, mark: Mark| {
+        if xs.contains(&x) && ys.contains(&y) {
+            cells[(y - ys.start) * xs.len() + (x - xs.start)] = symbol(mark);
+        }
+    };
+    for &coords in overlay.generated {
+        draw(coords, Mark::Generated);
+    }
+    for &coords in overlay.expanded {
+        draw(coords, Mark::Expanded);
+    }
+    for (i, &coords) in overlay.path.iter().enumerate() {
+        draw(coords, Mark::Path(coords, overlay.path.get(i + 1).copied()));
+    }
+    if let Some(start) = overlay.start {
+        draw(start, Mark::Start);
+    }
+    if let Some(goal) = overlay.goal {
+        draw(goal, Mark::Goal);
+    }
+}
+
+/// The arrow pointing from `from` to `to`.
+fn arrow(from: Coords2D, to: Coords2D) -> char {
+    use std::cmp::Ordering::*;
+    match (to.0.cmp(&from.0), to.1.cmp(&from.1)) {
+        (Greater, Equal) => '>',
+        (Less, Equal) => '<',
+        (Equal, Less) => '^',
+        (Equal, Greater) => 'v',
+        (Greater, Less) | (Less, Greater) => '/',
+        (Greater, Greater) | (Less, Less) => '\\',
+        (Equal, Equal) => PATH,
+    }
+}
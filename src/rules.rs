@@ -0,0 +1,129 @@
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::tile::Tile;
+
+/// The rules deciding which tiles can be traversed in a `MovingAiMap`.
+///
+/// The map takes care of the geometry (bounds and adjacency) and asks the
+/// rules about the tiles involved in a step. `MovingAiRules` implements the
+/// rules of the MovingAI benchmarks and it is the default for every map.
+///
+/// # Examples
+///
+/// Rules where trees can be traversed and everything else follows the
+/// MovingAI rules.
+///
+/// ```
+/// use movingai::{Map2D, MovingAiMap, MovingAiRules, Tile, TraversalRules};
+///
+/// struct Forest;
+///
+/// impl TraversalRules for Forest {
+///     fn is_passable(&self, tile: Tile) -> bool {
+///         tile == Tile::Tree || MovingAiRules.is_passable(tile)
+///     }
+///
+///     fn can_move(&self, from: Tile, to: Tile) -> bool {
+///         to == Tile::Tree || MovingAiRules.can_move(from, to)
+///     }
+/// }
+///
+/// let map = MovingAiMap::new(String::from("octile"), 1, 2, vec!['.', 'T']).unwrap();
+/// assert!(!map.is_traversable_from((0, 0), (1, 0)));
+/// let map = map.with_rules(Forest);
+/// assert!(map.is_traversable_from((0, 0), (1, 0)));
+/// ```
+pub trait TraversalRules {
+    /// Check if `tile` can be traversed **in some situation**.
+    fn is_passable(&self, tile: Tile) -> bool;
+
+    /// Check if an agent on `from` can move on the adjacent tile `to` with a
+    /// non-diagonal step.
+    fn can_move(&self, from: Tile, to: Tile) -> bool;
+
+    /// Check if an agent on `from` can move on the tile `to` with a diagonal
+    /// step. `side_a` and `side_b` are the two tiles adjacent to both `from`
+    /// and `to`.
+    ///
+    /// By default, a path through a side is free if the agent can reach `to`
+    /// through it with two non-diagonal steps, and `policy` decides how many
+    /// free sides are needed (see `DiagonalPolicy`).
+    fn can_move_diagonally(
+        &self,
+        policy: DiagonalPolicy,
+        from: Tile,
+        to: Tile,
+        side_a: Tile,
+        side_b: Tile,
+    ) -> bool {
+        let through = |side: Tile| self.can_move(from, side) && self.can_move(side, to);
+        match policy {
+            DiagonalPolicy::NoCornerCutting => through(side_a) && through(side_b),
+            DiagonalPolicy::OneSideFree => through(side_a) || through(side_b),
+            DiagonalPolicy::AlwaysAllow => self.can_move(from, to),
+        }
+    }
+}
+
+/// How diagonal steps on octile maps deal with the corners they cut.
+///
+/// ```text
+/// xb
+/// ay
+/// ```
+///
+/// Moving diagonally from `a` to `b` cuts the corners `x` and `y`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum DiagonalPolicy {
+    /// The step is allowed only if both `x` and `y` are free. This is the
+    /// MovingAI convention.
+    #[default]
+    NoCornerCutting,
+    /// The step is allowed if at least one between `x` and `y` is free.
+    OneSideFree,
+    /// The step is allowed regardless of `x` and `y`.
+    AlwaysAllow,
+}
+
+/// The traversal rules of the MovingAI benchmarks.
+///
+///  - Regular terrain (`.` and `G`) can always be traversed.
+///  - A water tile (`W`) can be traversed only coming from another water tile.
+///  - A swamp tile (`S`) can be traversed only coming from `.` or another swamp tile.
+///  - Everything else cannot be traversed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct MovingAiRules;
+
+impl TraversalRules for MovingAiRules {
+    fn is_passable(&self, tile: Tile) -> bool {
+        tile.is_passable()
+    }
+
+    fn can_move(&self, from: Tile, to: Tile) -> bool {
+        matches!(
+            (to, from),
+            (Tile::Free | Tile::Ground, _)
+                | (Tile::Swamp, Tile::Free | Tile::Swamp)
+                | (Tile::Water, Tile::Water)
+        )
+    }
+}
+
+/// Traversal rules where all the passable tiles (`.`, `G`, `S` and `W`) are
+/// equivalent and can be traversed from each other.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct UniformRules;
+
+impl TraversalRules for UniformRules {
+    fn is_passable(&self, tile: Tile) -> bool {
+        tile.is_passable()
+    }
+
+    fn can_move(&self, from: Tile, to: Tile) -> bool {
+        from.is_passable() && to.is_passable()
+    }
+}
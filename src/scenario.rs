@@ -0,0 +1,385 @@
+use std::fmt;
+
+use crate::components::label_components;
+use crate::map2d::{Coords2D, Map2D, MovingAiMap, SceneRecord};
+use crate::rules::TraversalRules;
+use crate::search::{dijkstra, AStar};
+
+/// The length of the paths in every bucket of a scenario.
+pub const BUCKET_LENGTH: f64 = 4.0;
+
+/// Options for generating a scenario.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GeneratorOptions {
+    /// The seed of the random number generator. The same seed on the same
+    /// map always produces the same scenario.
+    pub seed: u64,
+    /// The number of queries in every bucket. Defaults to `10`, as in the
+    /// MovingAI benchmarks.
+    pub queries_per_bucket: usize,
+    /// The maximum number of random start tiles to try. Every start runs a
+    /// Dijkstra search over the whole map, so this bounds the cost of the
+    /// generation on large maps. Defaults to `1000`.
+    pub max_starts: usize,
+    /// Stop after this many consecutive starts that do not fill the next
+    /// bucket, once the first one is full. The longest buckets are rare and
+    /// may never be filled: this avoids trying all the `max_starts` for
+    /// them. Defaults to `100`.
+    pub max_stale_starts: usize,
+}
+
+impl Default for GeneratorOptions {
+    fn default() -> GeneratorOptions {
+        GeneratorOptions {
+            seed: 0,
+            queries_per_bucket: 10,
+            max_starts: 1000,
+            max_stale_starts: 100,
+        }
+    }
+}
+
+/// Generate a scenario for `map` following the methodology of the MovingAI
+/// benchmarks.
+///
+/// Every record joins a random start with a random goal in the same strongly
+/// connected component. The optimal length is computed exactly and the
+/// bucket is `floor(optimal_length / 4)`. Every bucket gets
+/// `queries_per_bucket` records: the scenario contains the buckets from `0`
+/// up to the last one that could be filled, sorted by bucket. The generation
+/// stops when all the buckets are full, after `max_starts` starts or after
+/// `max_stale_starts` starts that did not fill the next bucket.
+///
+/// # Arguments
+///  * `map_file` the name of the map stored in the records.
+///
+/// # Examples
+///
+/// ```
+/// use std::path::Path;
+/// use movingai::parser::{parse_map_file, parse_scen, write_scen, SCEN_PRECISION};
+/// use movingai::scenario::{generate_scenario, GeneratorOptions};
+///
+/// let map = parse_map_file(Path::new("./tests/arena.map")).unwrap();
+/// let options = GeneratorOptions { seed: 42, ..GeneratorOptions::default() };
+/// let scen = generate_scenario(&map, "arena.map", &options);
+/// assert_eq!(scen.len() % 10, 0);
+/// assert!(scen.iter().all(|r| r.bucket == (r.optimal_length / 4.0) as u32));
+/// let contents = write_scen(&scen, SCEN_PRECISION);
+/// assert_eq!(parse_scen(&contents).unwrap().len(), scen.len());
+/// ```
+pub fn generate_scenario<R: TraversalRules>(
+    map: &MovingAiMap<R>,
+    map_file: &str,
+    options: &GeneratorOptions,
+) -> Vec<SceneRecord> {
+    let per_bucket = options.queries_per_bucket;
+    let mut rng = SplitMix64::new(options.seed);
+    let components = label_components(map);
+    // Starts must have somewhere to go.
+    let starts: Vec<Coords2D> = map
+        .coords()
+        .filter(|&c| {
+            components
+                .component(c)
+                .is_some_and(|component| components.sizes()[component] > 1)
+        })
+        .collect();
+    if starts.is_empty() || per_bucket == 0 {
+        return Vec::new();
+    }
+    let mut buckets: Vec<Vec<SceneRecord>> = Vec::new();
+    let full_buckets = |buckets: &[Vec<SceneRecord>]| {
+        buckets
+            .iter()
+            .take_while(|records| records.len() >= per_bucket)
+            .count()
+    };
+    let mut filled = 0;
+    let mut stale_starts = 0;
+
+    for _ in 0..options.max_starts {
+        let start = starts[rng.below(starts.len())];
+        let distances = dijkstra(map, start, false);
+        let mut goals: Vec<Vec<Coords2D>> = Vec::new();
+        for goal in map.coords() {
+            if goal == start || !components.same_component(start, goal) {
+                continue;
+            }
+            let bucket = bucket_of(distances.distance(goal));
+            if goals.len() <= bucket {
+                goals.resize(bucket + 1, Vec::new());
+            }
+            goals[bucket].push(goal);
+        }
+        if buckets.len() < goals.len() {
+            buckets.resize(goals.len(), Vec::new());
+        }
+        // One query per bucket from every start, to spread the starts.
+        for (bucket, candidates) in goals.iter().enumerate() {
+            if candidates.is_empty() || buckets[bucket].len() >= per_bucket {
+                continue;
+            }
+            let goal = candidates[rng.below(candidates.len())];
+            buckets[bucket].push(SceneRecord {
+                bucket: bucket as u32,
+                map_file: map_file.to_string(),
+                map_width: map.width(),
+                map_height: map.height(),
+                start_pos: start,
+                goal_pos: goal,
+                optimal_length: distances.distance(goal),
+            });
+        }
+        if buckets.iter().all(|records| records.len() >= per_bucket) {
+            break;
+        }
+        let now_filled = full_buckets(&buckets);
+        if now_filled > filled {
+            filled = now_filled;
+            stale_starts = 0;
+        } else {
+            stale_starts += 1;
+            if filled > 0 && stale_starts >= options.max_stale_starts {
+                break;
+            }
+        }
+    }
+
+    let filled = full_buckets(&buckets);
+    buckets.into_iter().take(filled).flatten().collect()
+}
+
+/// One of the two ends of a scenario record.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Endpoint {
+    /// The start position.
+    Start,
+    /// The goal position.
+    Goal,
+}
+
+impl fmt::Display for Endpoint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Endpoint::Start => write!(f, "start"),
+            Endpoint::Goal => write!(f, "goal"),
+        }
+    }
+}
+
+/// A problem of a scenario record with respect to its map.
+///
+/// `index` is the position of the record in the scenario.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ScenarioIssue {
+    /// The size stored in the record is not the size of the map.
+    DimensionMismatch {
+        /// The position of the record.
+        index: usize,
+        /// The width and height stored in the record.
+        record: (usize, usize),
+        /// The width and height of the map.
+        map: (usize, usize),
+    },
+    /// An end of the record is out of the map.
+    OutOfBounds {
+        /// The position of the record.
+        index: usize,
+        /// The end out of the map.
+        endpoint: Endpoint,
+        /// Its coordinates.
+        coords: Coords2D,
+    },
+    /// An end of the record cannot be traversed.
+    Blocked {
+        /// The position of the record.
+        index: usize,
+        /// The blocked end.
+        endpoint: Endpoint,
+        /// Its coordinates.
+        coords: Coords2D,
+    },
+    /// The goal cannot be reached from the start.
+    Unreachable {
+        /// The position of the record.
+        index: usize,
+    },
+    /// The optimal length of the record is not the length of the shortest
+    /// path on the map.
+    LengthMismatch {
+        /// The position of the record.
+        index: usize,
+        /// The optimal length stored in the record.
+        record: f64,
+        /// The length of the shortest path.
+        map: f64,
+    },
+}
+
+impl ScenarioIssue {
+    /// The position of the record with the issue.
+    pub fn index(&self) -> usize {
+        match self {
+            ScenarioIssue::DimensionMismatch { index, .. }
+            | ScenarioIssue::OutOfBounds { index, .. }
+            | ScenarioIssue::Blocked { index, .. }
+            | ScenarioIssue::Unreachable { index }
+            | ScenarioIssue::LengthMismatch { index, .. } => *index,
+        }
+    }
+}
+
+impl fmt::Display for ScenarioIssue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ScenarioIssue::DimensionMismatch { index, record, map } => write!(
+                f,
+                "record {}: size {}x{} does not match the map size {}x{}",
+                index, record.0, record.1, map.0, map.1
+            ),
+            ScenarioIssue::OutOfBounds {
+                index,
+                endpoint,
+                coords,
+            } => write!(
+                f,
+                "record {}: {} {:?} is out of the map",
+                index, endpoint, coords
+            ),
+            ScenarioIssue::Blocked {
+                index,
+                endpoint,
+                coords,
+            } => write!(
+                f,
+                "record {}: {} {:?} cannot be traversed",
+                index, endpoint, coords
+            ),
+            ScenarioIssue::Unreachable { index } => {
+                write!(f, "record {}: the goal cannot be reached", index)
+            }
+            ScenarioIssue::LengthMismatch { index, record, map } => write!(
+                f,
+                "record {}: optimal length is {}, but the shortest path is {}",
+                index, record, map
+            ),
+        }
+    }
+}
+
+/// Check the records of a scenario against their map.
+///
+/// It reports records whose size is not the size of the map, whose start or
+/// goal are out of the map or blocked, whose goal cannot be reached, and
+/// whose optimal length differs by more than `epsilon` from the length of
+/// the shortest path.
+///
+/// # Returns
+///  All the issues found, in the order of the records. The scenario is valid
+///  if it is empty.
+///
+/// # Examples
+///
+/// ```
+/// use std::path::Path;
+/// use movingai::parser::{parse_map_file, parse_scen_file};
+/// use movingai::scenario::{verify_scenario, ScenarioIssue};
+///
+/// let map = parse_map_file(Path::new("./tests/arena.map")).unwrap();
+/// let mut scen = parse_scen_file(Path::new("./tests/arena.map.scen")).unwrap();
+/// assert!(verify_scenario(&map, &scen, 0.0001).is_empty());
+///
+/// scen[3].optimal_length += 1.0;
+/// let issues = verify_scenario(&map, &scen, 0.0001);
+/// assert!(matches!(issues[..], [ScenarioIssue::LengthMismatch { index: 3, .. }]));
+/// ```
+pub fn verify_scenario<R: TraversalRules>(
+    map: &MovingAiMap<R>,
+    records: &[SceneRecord],
+    epsilon: f64,
+) -> Vec<ScenarioIssue> {
+    let components = label_components(map);
+    let mut search = AStar::new();
+    let mut issues = Vec::new();
+    for (index, record) in records.iter().enumerate() {
+        let size = (map.width(), map.height());
+        if (record.map_width, record.map_height) != size {
+            issues.push(ScenarioIssue::DimensionMismatch {
+                index,
+                record: (record.map_width, record.map_height),
+                map: size,
+            });
+        }
+        let mut valid_ends = true;
+        for (endpoint, coords) in [
+            (Endpoint::Start, record.start_pos),
+            (Endpoint::Goal, record.goal_pos),
+        ] {
+            if map.is_out_of_bound(coords) {
+                issues.push(ScenarioIssue::OutOfBounds {
+                    index,
+                    endpoint,
+                    coords,
+                });
+                valid_ends = false;
+            } else if !map.is_traversable(coords) {
+                issues.push(ScenarioIssue::Blocked {
+                    index,
+                    endpoint,
+                    coords,
+                });
+                valid_ends = false;
+            }
+        }
+        if !valid_ends {
+            continue;
+        }
+        if !components.can_reach(record.start_pos, record.goal_pos) {
+            issues.push(ScenarioIssue::Unreachable { index });
+            continue;
+        }
+        match search.search(map, record.start_pos, record.goal_pos) {
+            Some(result) if (result.cost - record.optimal_length).abs() > epsilon => {
+                issues.push(ScenarioIssue::LengthMismatch {
+                    index,
+                    record: record.optimal_length,
+                    map: result.cost,
+                });
+            }
+            Some(_) => {}
+            None => issues.push(ScenarioIssue::Unreachable { index }),
+        }
+    }
+    issues
+}
+
+/// The bucket of a path of the given length.
+fn bucket_of(length: f64) -> usize {
+    (length / BUCKET_LENGTH).floor() as usize
+}
+
+/// A small, seedable random number generator (SplitMix64).
+#[derive(Debug)]
+struct SplitMix64 {
+    state: u64,
+}
+
+impl SplitMix64 {
+    fn new(seed: u64) -> SplitMix64 {
+        SplitMix64 { state: seed }
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// A random number in `0..n`.
+    fn below(&mut self, n: usize) -> usize {
+        ((self.next_u64() as u128 * n as u128) >> 64) as usize
+    }
+}
@@ -0,0 +1,452 @@
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+
+use crate::map2d::{Coords2D, Map2D};
+
+mod jps;
+mod jps_plus;
+
+pub use jps::*;
+pub use jps_plus::*;
+
+/// The result of a successful search.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SearchResult {
+    /// The path from the start to the goal (both included).
+    pub path: Vec<Coords2D>,
+    /// The cost of the path.
+    pub cost: f64,
+    /// The number of nodes expanded by the search.
+    pub expanded: usize,
+}
+
+/// Find a shortest path between `start` and `goal` with A*.
+///
+/// The available neighbors and the cost of the steps are decided by the map
+/// (see `Map2D::neighbors`, `Map2D::knight_neighbors` and
+/// `Map2D::step_cost`). By default, moving to a cardinal neighbor costs `1`
+/// and moving to a diagonal neighbor costs `sqrt(2)`, matching the optimal
+/// lengths of the MovingAI benchmarks.
+///
+/// It uses the heuristic of the map (see `Map2D::heuristic`). To run many
+/// queries, use an `AStar` to reuse its memory across them.
+///
+/// # Returns
+///  The path and its cost, or `None` if `goal` cannot be reached (or `start`
+///  or `goal` are out of bound, or `start` cannot be traversed).
+///
+/// # Examples
+///
+/// ```
+/// use std::path::Path;
+/// use movingai::parser::parse_map_file;
+/// use movingai::search::astar;
+///
+/// let map = parse_map_file(Path::new("./tests/arena.map")).unwrap();
+/// let result = astar(&map, (1, 11), (1, 12)).unwrap();
+/// assert_eq!(result.path, vec![(1, 11), (1, 12)]);
+/// assert_eq!(result.cost, 1.0);
+/// ```
+pub fn astar<T, M: Map2D<T>>(map: &M, start: Coords2D, goal: Coords2D) -> Option<SearchResult> {
+    AStar::new().search(map, start, goal)
+}
+
+/// Find a shortest path between `start` and `goal` with A* using a custom
+/// heuristic.
+///
+/// # Arguments
+///  * `heuristic` estimates the cost between two coordinates. The returned
+///    path is optimal only if the heuristic is consistent: for every step
+///    from `a` to `b`, `heuristic(a, goal) <= step_cost(a, b) +
+///    heuristic(b, goal)`. Closed nodes are never reopened, so a heuristic
+///    that is only admissible (it never overestimates the real cost) may
+///    return a suboptimal path.
+///
+/// # Returns
+///  The path and its cost, or `None` if `goal` cannot be reached (or `start`
+///  or `goal` are out of bound, or `start` cannot be traversed).
+pub fn astar_with_heuristic<T, M, H>(
+    map: &M,
+    start: Coords2D,
+    goal: Coords2D,
+    heuristic: H,
+) -> Option<SearchResult>
+where
+    M: Map2D<T>,
+    H: Fn(Coords2D, Coords2D) -> f64,
+{
+    AStar::new().search_with_heuristic(map, start, goal, heuristic)
+}
+
+/// A reusable A* search.
+///
+/// It keeps its per-tile memory between searches, so that running many
+/// queries does not pay for allocating and clearing it every time.
+///
+/// # Examples
+///
+/// ```
+/// use std::path::Path;
+/// use movingai::parser::{parse_map_file, parse_scen_file};
+/// use movingai::search::AStar;
+///
+/// let map = parse_map_file(Path::new("./tests/arena.map")).unwrap();
+/// let scen = parse_scen_file(Path::new("./tests/arena.map.scen")).unwrap();
+/// let mut search = AStar::new();
+/// for record in &scen {
+///     let result = search.search(&map, record.start_pos, record.goal_pos).unwrap();
+///     assert!((result.cost - record.optimal_length).abs() < 0.0001);
+/// }
+/// ```
+#[derive(Debug, Default)]
+pub struct AStar {
+    nodes: NodeTable,
+    open: BinaryHeap<Node>,
+}
+
+impl AStar {
+    /// Create a new `AStar`.
+    pub fn new() -> AStar {
+        AStar::default()
+    }
+
+    /// Same as `astar`, reusing the memory of this `AStar`.
+    pub fn search<T, M: Map2D<T>>(
+        &mut self,
+        map: &M,
+        start: Coords2D,
+        goal: Coords2D,
+    ) -> Option<SearchResult> {
+        self.search_with_heuristic(map, start, goal, |a, b| map.heuristic(a, b))
+    }
+
+    /// Same as `astar_with_heuristic`, reusing the memory of this `AStar`.
+    pub fn search_with_heuristic<T, M, H>(
+        &mut self,
+        map: &M,
+        start: Coords2D,
+        goal: Coords2D,
+        heuristic: H,
+    ) -> Option<SearchResult>
+    where
+        M: Map2D<T>,
+        H: Fn(Coords2D, Coords2D) -> f64,
+    {
+        if map.is_out_of_bound(start) || map.is_out_of_bound(goal) || !map.is_traversable(start) {
+            return None;
+        }
+        let width = map.width();
+        let index = |(x, y): Coords2D| y * width + x;
+        let nodes = &mut self.nodes;
+        let open = &mut self.open;
+        nodes.reset(width * map.height());
+        open.clear();
+        let mut expanded = 0;
+
+        nodes.update(index(start), 0.0, start);
+        open.push(Node {
+            f: heuristic(start, goal),
+            g: 0.0,
+            coords: start,
+        });
+
+        while let Some(Node {
+            g: cost, coords, ..
+        }) = open.pop()
+        {
+            let current = index(coords);
+            if nodes.is_closed(current) {
+                continue;
+            }
+            if coords == goal {
+                return Some(SearchResult {
+                    path: nodes.path(width, goal),
+                    cost,
+                    expanded,
+                });
+            }
+            nodes.close(current);
+            expanded += 1;
+            let neighbors = map.neighbors(coords).into_iter();
+            for neighbor in neighbors.chain(map.knight_neighbors(coords)) {
+                let next = index(neighbor);
+                let tentative = cost + map.step_cost(coords, neighbor);
+                if !nodes.is_closed(next) && tentative < nodes.g(next) {
+                    nodes.update(next, tentative, coords);
+                    open.push(Node {
+                        f: tentative + heuristic(neighbor, goal),
+                        g: tentative,
+                        coords: neighbor,
+                    });
+                }
+            }
+        }
+
+        None
+    }
+}
+
+/// The distances from a source tile to all the tiles of a map, computed
+/// by `dijkstra`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DistanceMap {
+    source: Coords2D,
+    width: usize,
+    height: usize,
+    distances: Vec<f64>,
+    predecessors: Option<Vec<Option<Coords2D>>>,
+}
+
+impl DistanceMap {
+    /// The source of the distances.
+    pub fn source(&self) -> Coords2D {
+        self.source
+    }
+
+    /// The width of the map.
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    /// The height of the map.
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// The cost of a shortest path from the source to `coords`.
+    ///
+    /// It is infinity if `coords` is unreachable or out of bound.
+    pub fn distance(&self, coords: Coords2D) -> f64 {
+        if coords.0 >= self.width || coords.1 >= self.height {
+            return f64::INFINITY;
+        }
+        self.distances[coords.1 * self.width + coords.0]
+    }
+
+    /// All the distances, in row-major order.
+    pub fn distances(&self) -> &[f64] {
+        &self.distances
+    }
+
+    /// The tile preceding `coords` on a shortest path from the source.
+    ///
+    /// It is `None` for the source, for unreachable tiles and if the
+    /// predecessors have not been computed.
+    pub fn predecessor(&self, coords: Coords2D) -> Option<Coords2D> {
+        if coords.0 >= self.width || coords.1 >= self.height {
+            return None;
+        }
+        self.predecessors.as_ref()?[coords.1 * self.width + coords.0]
+    }
+
+    /// Check if the predecessors have been computed.
+    pub fn has_predecessors(&self) -> bool {
+        self.predecessors.is_some()
+    }
+
+    /// A shortest path from the source to `goal` (both included).
+    ///
+    /// It is `None` if `goal` is unreachable or if the predecessors have not
+    /// been computed.
+    pub fn path_to(&self, goal: Coords2D) -> Option<Vec<Coords2D>> {
+        if !self.has_predecessors() || !self.distance(goal).is_finite() {
+            return None;
+        }
+        let mut path = vec![goal];
+        let mut current = goal;
+        while let Some(predecessor) = self.predecessor(current) {
+            path.push(predecessor);
+            current = predecessor;
+        }
+        path.reverse();
+        Some(path)
+    }
+}
+
+/// Compute the distances from `source` to every tile of the map with
+/// Dijkstra's algorithm.
+///
+/// Costs are the same used by `astar`. If `source` is out of bound or cannot
+/// be traversed, every distance is infinite.
+///
+/// # Arguments
+///  * `source` the tile where all the paths start.
+///  * `predecessors` if `true`, also store the predecessor of every tile, so
+///    that `DistanceMap::path_to` can rebuild the shortest paths.
+///
+/// # Examples
+///
+/// ```
+/// use std::path::Path;
+/// use movingai::parser::parse_map_file;
+/// use movingai::search::dijkstra;
+///
+/// let map = parse_map_file(Path::new("./tests/arena.map")).unwrap();
+/// let distances = dijkstra(&map, (1, 11), true);
+/// assert_eq!(distances.distance((1, 12)), 1.0);
+/// assert!(distances.distance((0, 0)).is_infinite());
+/// assert_eq!(distances.path_to((1, 12)), Some(vec![(1, 11), (1, 12)]));
+/// ```
+pub fn dijkstra<T, M: Map2D<T>>(map: &M, source: Coords2D, predecessors: bool) -> DistanceMap {
+    let width = map.width();
+    let height = map.height();
+    let index = |(x, y): Coords2D| y * width + x;
+    let mut distances = vec![f64::INFINITY; width * height];
+    let mut parents = if predecessors {
+        Some(vec![None; width * height])
+    } else {
+        None
+    };
+
+    if !map.is_out_of_bound(source) && map.is_traversable(source) {
+        let mut closed = vec![false; width * height];
+        let mut open = BinaryHeap::new();
+        distances[index(source)] = 0.0;
+        open.push(Node {
+            f: 0.0,
+            g: 0.0,
+            coords: source,
+        });
+        while let Some(Node {
+            g: cost, coords, ..
+        }) = open.pop()
+        {
+            let current = index(coords);
+            if closed[current] {
+                continue;
+            }
+            closed[current] = true;
+            let neighbors = map.neighbors(coords).into_iter();
+            for neighbor in neighbors.chain(map.knight_neighbors(coords)) {
+                let next = index(neighbor);
+                let tentative = cost + map.step_cost(coords, neighbor);
+                if tentative < distances[next] {
+                    distances[next] = tentative;
+                    if let Some(parents) = parents.as_mut() {
+                        parents[next] = Some(coords);
+                    }
+                    open.push(Node {
+                        f: tentative,
+                        g: tentative,
+                        coords: neighbor,
+                    });
+                }
+            }
+        }
+    }
+
+    DistanceMap {
+        source,
+        width,
+        height,
+        distances,
+        predecessors: parents,
+    }
+}
+
+/// The per-tile data of a search.
+///
+/// Instead of clearing everything before every search, each entry is tagged
+/// with the search generation that wrote it: entries of older generations
+/// are considered unvisited.
+#[derive(Debug, Default)]
+struct NodeTable {
+    generation: u32,
+    /// The generation in which the entry has been reached.
+    reached: Vec<u32>,
+    /// The generation in which the entry has been closed.
+    closed: Vec<u32>,
+    g: Vec<f64>,
+    /// The parent of every reached entry. The start is its own parent.
+    parents: Vec<Coords2D>,
+}
+
+impl NodeTable {
+    /// Prepare the table for a new search over `size` tiles.
+    fn reset(&mut self, size: usize) {
+        if self.generation == u32::MAX || self.reached.len() < size {
+            self.generation = 0;
+            self.reached = vec![0; size];
+            self.closed = vec![0; size];
+            self.g = vec![0.0; size];
+            self.parents = vec![(0, 0); size];
+        }
+        self.generation += 1;
+    }
+
+    fn g(&self, index: usize) -> f64 {
+        if self.reached[index] == self.generation {
+            self.g[index]
+        } else {
+            f64::INFINITY
+        }
+    }
+
+    fn update(&mut self, index: usize, g: f64, parent: Coords2D) {
+        self.reached[index] = self.generation;
+        self.g[index] = g;
+        self.parents[index] = parent;
+    }
+
+    /// The parent of a reached entry.
+    fn parent(&self, index: usize) -> Coords2D {
+        self.parents[index]
+    }
+
+    fn is_closed(&self, index: usize) -> bool {
+        self.closed[index] == self.generation
+    }
+
+    fn close(&mut self, index: usize) {
+        self.closed[index] = self.generation;
+    }
+
+    /// Walk the parents back from `goal` to the start of the search.
+    fn path(&self, width: usize, goal: Coords2D) -> Vec<Coords2D> {
+        let mut path = vec![goal];
+        let mut current = goal;
+        loop {
+            let parent = self.parents[current.1 * width + current.0];
+            if parent == current {
+                break;
+            }
+            path.push(parent);
+            current = parent;
+        }
+        path.reverse();
+        path
+    }
+}
+
+/// A node in the open list. Nodes are ordered by lowest `f` first and, on
+/// ties, by highest `g` first (i.e., deeper nodes first).
+#[derive(Debug)]
+struct Node {
+    f: f64,
+    g: f64,
+    coords: Coords2D,
+}
+
+impl PartialEq for Node {
+    fn eq(&self, other: &Node) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Node {}
+
+impl PartialOrd for Node {
+    fn partial_cmp(&self, other: &Node) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Node {
+    fn cmp(&self, other: &Node) -> Ordering {
+        // This is reversed on purpose to make the max-heap into min-heap.
+        other
+            .f
+            .total_cmp(&self.f)
+            .then_with(|| self.g.total_cmp(&other.g))
+    }
+}
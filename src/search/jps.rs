@@ -0,0 +1,371 @@
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+
+use arrayvec::ArrayVec;
+
+use super::{AStar, Node, NodeTable, SearchResult};
+use crate::heuristics::octile;
+use crate::map2d::{Coords2D, Map2D, MapType, MovingAiMap};
+use crate::rules::{DiagonalPolicy, TraversalRules};
+use crate::tile::Tile;
+
+/// Find a shortest path between `start` and `goal` with Jump Point Search.
+///
+/// JPS finds paths as short as the ones of `astar` on octile maps where
+/// diagonal steps cannot cut corners, but it expands far fewer nodes. Use a
+/// `JumpPointSearch` to run many queries on the same map.
+///
+/// # Returns
+///  The path (every tile, not only the jump points) and its cost, or `None`
+///  if `goal` cannot be reached.
+///
+/// # Examples
+///
+/// ```
+/// use std::path::Path;
+/// use movingai::parser::parse_map_file;
+/// use movingai::search::jps;
+///
+/// let map = parse_map_file(Path::new("./tests/arena.map")).unwrap();
+/// let result = jps(&map, (1, 11), (1, 14)).unwrap();
+/// assert_eq!(result.path, vec![(1, 11), (1, 12), (1, 13), (1, 14)]);
+/// assert_eq!(result.cost, 3.0);
+/// ```
+pub fn jps<R: TraversalRules>(
+    map: &MovingAiMap<R>,
+    start: Coords2D,
+    goal: Coords2D,
+) -> Option<SearchResult> {
+    JumpPointSearch::new(map).search(start, goal)
+}
+
+/// A reusable Jump Point Search on a `MovingAiMap`.
+///
+/// This is JPS for octile grids without corner cutting (Harabor and Grastien,
+/// 2011, in the variant for grids where diagonal steps need both sides
+/// free). Jumps only look at which tiles are traversable, so the search is
+/// used when all the steps of the map follow from that, that is when:
+///
+///  - the map is octile and its diagonal policy is
+///    `DiagonalPolicy::NoCornerCutting`, and
+///  - the rules of the map allow to move between every pair of passable tiles
+///    on the map (e.g., a map with `.` and `T` tiles with the MovingAI rules).
+///
+/// On every other map, the search falls back on A*, so the results always
+/// follow `is_traversable_from`. `TraversalRules::can_move_diagonally` is
+/// assumed to keep its default behaviour.
+///
+/// # Examples
+///
+/// ```
+/// use std::path::Path;
+/// use movingai::parser::{parse_map_file, parse_scen_file};
+/// use movingai::search::JumpPointSearch;
+///
+/// let map = parse_map_file(Path::new("./tests/arena.map")).unwrap();
+/// let scen = parse_scen_file(Path::new("./tests/arena.map.scen")).unwrap();
+/// let mut search = JumpPointSearch::new(&map);
+/// assert!(search.uses_jumps());
+/// for record in &scen {
+///     let result = search.search(record.start_pos, record.goal_pos).unwrap();
+///     assert!((result.cost - record.optimal_length).abs() < 0.0001);
+/// }
+/// ```
+#[derive(Debug)]
+pub struct JumpPointSearch<'a, R> {
+    map: &'a MovingAiMap<R>,
+    /// Which tiles can be traversed, in row-major order. It is empty if the
+    /// search falls back on A*.
+    walkable: Vec<bool>,
+    nodes: NodeTable,
+    open: BinaryHeap<Node>,
+    fallback: AStar,
+}
+
+impl<'a, R: TraversalRules> JumpPointSearch<'a, R> {
+    /// Prepare a Jump Point Search on `map`.
+    pub fn new(map: &'a MovingAiMap<R>) -> JumpPointSearch<'a, R> {
+        let walkable = if supports_jumps(map) {
+            map.coords().map(|c| map.is_traversable(c)).collect()
+        } else {
+            Vec::new()
+        };
+        JumpPointSearch {
+            map,
+            walkable,
+            nodes: NodeTable::default(),
+            open: BinaryHeap::new(),
+            fallback: AStar::new(),
+        }
+    }
+
+    /// Check if the search uses jumps on this map, instead of falling back
+    /// on A*.
+    pub fn uses_jumps(&self) -> bool {
+        !self.walkable.is_empty()
+    }
+
+    /// Same as `jps`, reusing the memory of this `JumpPointSearch`.
+    pub fn search(&mut self, start: Coords2D, goal: Coords2D) -> Option<SearchResult> {
+        if !self.uses_jumps() {
+            return self.fallback.search(self.map, start, goal);
+        }
+        if self.map.is_out_of_bound(start) || self.map.is_out_of_bound(goal) {
+            return None;
+        }
+        if !self.is_walkable(start) || !self.is_walkable(goal) {
+            return None;
+        }
+        if start == goal {
+            return Some(SearchResult {
+                path: vec![start],
+                cost: 0.0,
+                expanded: 0,
+            });
+        }
+
+        let width = self.map.width();
+        let index = |(x, y): Coords2D| y * width + x;
+        self.nodes.reset(width * self.map.height());
+        self.open.clear();
+        let mut expanded = 0;
+
+        self.nodes.update(index(start), 0.0, start);
+        self.open.push(Node {
+            f: octile(start, goal),
+            g: 0.0,
+            coords: start,
+        });
+
+        while let Some(Node {
+            g: cost, coords, ..
+        }) = self.open.pop()
+        {
+            let current = index(coords);
+            if self.nodes.is_closed(current) {
+                continue;
+            }
+            if coords == goal {
+                let jump_points = self.nodes.path(width, goal);
+                return Some(SearchResult {
+                    path: fill_path(&jump_points),
+                    cost,
+                    expanded,
+                });
+            }
+            self.nodes.close(current);
+            expanded += 1;
+            let parent = self.nodes.parent(current);
+            let (x, y) = (coords.0 as isize, coords.1 as isize);
+            let walk = |dx, dy| self.walkable_at(x + dx, y + dy);
+            for direction in pruned_directions(coords, parent, walk) {
+                let Some(jump_point) = self.jump(coords, direction, goal) else {
+                    continue;
+                };
+                let next = index(jump_point);
+                let tentative = cost + octile(coords, jump_point);
+                if !self.nodes.is_closed(next) && tentative < self.nodes.g(next) {
+                    self.nodes.update(next, tentative, coords);
+                    self.open.push(Node {
+                        f: tentative + octile(jump_point, goal),
+                        g: tentative,
+                        coords: jump_point,
+                    });
+                }
+            }
+        }
+
+        None
+    }
+
+    fn is_walkable(&self, (x, y): Coords2D) -> bool {
+        self.walkable_at(x as isize, y as isize)
+    }
+
+    fn walkable_at(&self, x: isize, y: isize) -> bool {
+        x >= 0
+            && y >= 0
+            && (x as usize) < self.map.width()
+            && (y as usize) < self.map.height()
+            && self.walkable[y as usize * self.map.width() + x as usize]
+    }
+
+    /// Move from `from` towards `direction` until a jump point is found.
+    ///
+    /// The first step is assumed to be legal, diagonal ones included.
+    fn jump(&self, from: Coords2D, direction: (isize, isize), goal: Coords2D) -> Option<Coords2D> {
+        let (dx, dy) = direction;
+        let (mut x, mut y) = (from.0 as isize + dx, from.1 as isize + dy);
+        loop {
+            if !self.walkable_at(x, y) {
+                return None;
+            }
+            let coords = (x as usize, y as usize);
+            if coords == goal {
+                return Some(coords);
+            }
+            if dx != 0 && dy != 0 {
+                // A diagonal jump stops where a straight jump would find
+                // something.
+                if self.jump(coords, (dx, 0), goal).is_some()
+                    || self.jump(coords, (0, dy), goal).is_some()
+                {
+                    return Some(coords);
+                }
+            } else if has_forced_neighbor(|x, y| self.walkable_at(x, y), (x, y), direction) {
+                return Some(coords);
+            }
+            // Without corner cutting, the next step needs both sides free.
+            if !self.walkable_at(x + dx, y) || !self.walkable_at(x, y + dy) {
+                return None;
+            }
+            x += dx;
+            y += dy;
+        }
+    }
+}
+
+/// The directions to explore from `coords`, reached from `parent`.
+///
+/// `walk` tells if the tile at the given offset from `coords` can be
+/// traversed.
+pub(super) fn pruned_directions<W>(
+    coords: Coords2D,
+    parent: Coords2D,
+    walk: W,
+) -> ArrayVec<(isize, isize), 8>
+where
+    W: Fn(isize, isize) -> bool,
+{
+    let (x, y) = (coords.0 as isize, coords.1 as isize);
+    let mut directions = ArrayVec::new();
+    if parent == coords {
+        // The start: every legal step.
+        for (dx, dy) in [(1, 0), (-1, 0), (0, 1), (0, -1)] {
+            if walk(dx, dy) {
+                directions.push((dx, dy));
+            }
+        }
+        for (dx, dy) in [(1, 1), (1, -1), (-1, 1), (-1, -1)] {
+            if walk(dx, 0) && walk(0, dy) {
+                directions.push((dx, dy));
+            }
+        }
+        return directions;
+    }
+
+    let dx = (x - parent.0 as isize).signum();
+    let dy = (y - parent.1 as isize).signum();
+    if dx != 0 && dy != 0 {
+        if walk(0, dy) {
+            directions.push((0, dy));
+        }
+        if walk(dx, 0) {
+            directions.push((dx, 0));
+        }
+        if walk(dx, 0) && walk(0, dy) {
+            directions.push((dx, dy));
+        }
+    } else if dx != 0 {
+        if walk(dx, 0) {
+            directions.push((dx, 0));
+            if walk(0, 1) {
+                directions.push((dx, 1));
+            }
+            if walk(0, -1) {
+                directions.push((dx, -1));
+            }
+        }
+        if walk(0, 1) {
+            directions.push((0, 1));
+        }
+        if walk(0, -1) {
+            directions.push((0, -1));
+        }
+    } else {
+        if walk(0, dy) {
+            directions.push((0, dy));
+            if walk(1, 0) {
+                directions.push((1, dy));
+            }
+            if walk(-1, 0) {
+                directions.push((-1, dy));
+            }
+        }
+        if walk(1, 0) {
+            directions.push((1, 0));
+        }
+        if walk(-1, 0) {
+            directions.push((-1, 0));
+        }
+    }
+    directions
+}
+
+/// Check if a straight step in `direction` onto `(x, y)` has forced
+/// neighbours, that is if a tile beside it is free and the tile behind that
+/// one is blocked.
+pub(super) fn has_forced_neighbor<W>(
+    walkable_at: W,
+    (x, y): (isize, isize),
+    direction: (isize, isize),
+) -> bool
+where
+    W: Fn(isize, isize) -> bool,
+{
+    let (dx, dy) = direction;
+    if dx != 0 {
+        (walkable_at(x, y - 1) && !walkable_at(x - dx, y - 1))
+            || (walkable_at(x, y + 1) && !walkable_at(x - dx, y + 1))
+    } else {
+        (walkable_at(x - 1, y) && !walkable_at(x - 1, y - dy))
+            || (walkable_at(x + 1, y) && !walkable_at(x + 1, y - dy))
+    }
+}
+
+/// Check if every step on `map` can be decided by looking only at which tiles
+/// are passable.
+pub(super) fn supports_jumps<R: TraversalRules>(map: &MovingAiMap<R>) -> bool {
+    if map.map_type() != MapType::Octile || map.diagonal_policy() != DiagonalPolicy::NoCornerCutting
+    {
+        return false;
+    }
+    let mut present = [false; 256];
+    for coords in map.coords() {
+        present[map.tile_byte(coords) as usize] = true;
+    }
+    let rules = map.rules();
+    let passable: Vec<Tile> = (0..=255u8)
+        .filter(|&byte| present[byte as usize])
+        .map(|byte| Tile::from(byte as char))
+        .filter(|&tile| rules.is_passable(tile))
+        .collect();
+    passable
+        .iter()
+        .all(|&from| passable.iter().all(|&to| rules.can_move(from, to)))
+}
+
+/// Expand a list of jump points in the list of all the tiles between them.
+///
+/// Consecutive jump points are always on a straight or diagonal line.
+pub(super) fn fill_path(jump_points: &[Coords2D]) -> Vec<Coords2D> {
+    let mut path = vec![jump_points[0]];
+    for pair in jump_points.windows(2) {
+        let (mut x, mut y) = pair[0];
+        let (p, q) = pair[1];
+        while (x, y) != (p, q) {
+            x = step_towards(x, p);
+            y = step_towards(y, q);
+            path.push((x, y));
+        }
+    }
+    path
+}
+
+fn step_towards(from: usize, to: usize) -> usize {
+    match from.cmp(&to) {
+        Ordering::Less => from + 1,
+        Ordering::Equal => from,
+        Ordering::Greater => from - 1,
+    }
+}
@@ -0,0 +1,458 @@
+use std::collections::BinaryHeap;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use super::jps::{fill_path, has_forced_neighbor, pruned_directions, supports_jumps};
+use super::{AStar, Node, NodeTable, SearchResult};
+use crate::heuristics::octile;
+use crate::map2d::{Coords2D, Map2D, MovingAiMap};
+use crate::rules::TraversalRules;
+
+/// The eight directions, in the order used by `JumpTable`.
+const DIRECTIONS: [(isize, isize); 8] = [
+    (1, 0),
+    (-1, 0),
+    (0, 1),
+    (0, -1),
+    (1, 1),
+    (1, -1),
+    (-1, 1),
+    (-1, -1),
+];
+
+/// The jump distances of a map, precomputed for JPS+.
+///
+/// For every tile and every direction, the table stores how far a jump goes:
+/// a positive distance `n` means that the jump stops on a jump point after
+/// `n` steps, while zero or a negative distance `-n` means that the jump
+/// hits an obstacle after `n` steps without finding anything.
+///
+/// Building the table takes time on large maps. With the `serde` feature,
+/// the table can be serialized and loaded again later; `matches` checks that
+/// a loaded table has been built for a given map. Loading a table whose
+/// distances do not fit its size fails.
+///
+/// # Examples
+///
+/// ```
+/// use std::path::Path;
+/// use movingai::parser::parse_map_file;
+/// use movingai::search::JumpTable;
+///
+/// let map = parse_map_file(Path::new("./tests/arena.map")).unwrap();
+/// let table = JumpTable::new(&map);
+/// assert!(table.matches(&map));
+/// // Moving east from (1, 11), an obstacle comes after 46 steps and there
+/// // is no jump point before it.
+/// assert_eq!(table.distance((1, 11), (1, 0)), -46);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "JumpTableData"))]
+pub struct JumpTable {
+    width: usize,
+    height: usize,
+    /// A hash of the traversable tiles of the map.
+    fingerprint: u64,
+    distances: Vec<[i32; 8]>,
+}
+
+impl JumpTable {
+    /// Compute the jump distances of `map`.
+    pub fn new<R: TraversalRules>(map: &MovingAiMap<R>) -> JumpTable {
+        let width = map.width();
+        let height = map.height();
+        let walkable: Vec<bool> = map.coords().map(|c| map.is_traversable(c)).collect();
+        let walkable_at = |x: isize, y: isize| {
+            x >= 0
+                && y >= 0
+                && (x as usize) < width
+                && (y as usize) < height
+                && walkable[y as usize * width + x as usize]
+        };
+        let mut distances = vec![[0; 8]; width * height];
+
+        // Straight directions first, as diagonal jumps are built on them.
+        for (d, &direction) in DIRECTIONS.iter().enumerate() {
+            let (dx, dy) = direction;
+            // Visit the tiles so that the next tile in `direction` has
+            // already been computed.
+            for y in ordered(height, dy) {
+                for x in ordered(width, dx) {
+                    let (x, y) = (x as isize, y as isize);
+                    let (nx, ny) = (x + dx, y + dy);
+                    if !walkable_at(x, y) || !walkable_at(nx, ny) {
+                        continue;
+                    }
+                    let next = ny as usize * width + nx as usize;
+                    let found = if dx != 0 && dy != 0 {
+                        if !walkable_at(nx, y) || !walkable_at(x, ny) {
+                            continue;
+                        }
+                        let [horizontal, vertical] = [straight(dx, 0), straight(0, dy)];
+                        distances[next][horizontal] > 0 || distances[next][vertical] > 0
+                    } else {
+                        has_forced_neighbor(walkable_at, (nx, ny), direction)
+                    };
+                    let distance = match distances[next][d] {
+                        _ if found => 1,
+                        n if n > 0 => n + 1,
+                        n => n - 1,
+                    };
+                    distances[y as usize * width + x as usize][d] = distance;
+                }
+            }
+        }
+
+        JumpTable {
+            width,
+            height,
+            fingerprint: fingerprint(width, height, &walkable),
+            distances,
+        }
+    }
+
+    /// The width of the map.
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    /// The height of the map.
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// The jump distance from `coords` towards `direction` (one of the eight
+    /// `(dx, dy)` steps).
+    ///
+    /// # Panics
+    ///
+    /// Panics if `coords` is out of bound or `direction` is not a step.
+    pub fn distance(&self, coords: Coords2D, direction: (isize, isize)) -> i32 {
+        let d = DIRECTIONS
+            .iter()
+            .position(|&step| step == direction)
+            .expect("the direction must be one of the eight steps");
+        self.distances[self.index(coords)][d]
+    }
+
+    /// Check if the table has been computed for `map` (or for a map with the
+    /// same size and traversable tiles).
+    ///
+    /// It also checks that the table has a distance for every tile and that
+    /// no jump leaves the map.
+    pub fn matches<R: TraversalRules>(&self, map: &MovingAiMap<R>) -> bool {
+        if self.width != map.width() || self.height != map.height() || !self.is_consistent() {
+            return false;
+        }
+        let walkable: Vec<bool> = map.coords().map(|c| map.is_traversable(c)).collect();
+        self.fingerprint == fingerprint(self.width, self.height, &walkable)
+    }
+
+    /// Check that there is a distance for every tile and that every jump
+    /// stays inside the map.
+    fn is_consistent(&self) -> bool {
+        if self.width.checked_mul(self.height) != Some(self.distances.len()) {
+            return false;
+        }
+        let inside = |start: usize, step: isize, distance: i32, size: usize| {
+            let reach = distance.unsigned_abs() as usize;
+            match step {
+                1 => start.checked_add(reach).is_some_and(|end| end < size),
+                -1 => reach <= start,
+                _ => true,
+            }
+        };
+        self.distances.iter().enumerate().all(|(i, distances)| {
+            let (x, y) = (i % self.width, i / self.width);
+            DIRECTIONS
+                .iter()
+                .zip(distances)
+                .all(|(&(dx, dy), &distance)| {
+                    inside(x, dx, distance, self.width) && inside(y, dy, distance, self.height)
+                })
+        })
+    }
+
+    fn index(&self, coords: Coords2D) -> usize {
+        assert!(coords.0 < self.width && coords.1 < self.height);
+        coords.1 * self.width + coords.0
+    }
+}
+
+/// The serialized fields of a `JumpTable`, checked before building it.
+#[cfg(feature = "serde")]
+#[derive(Deserialize)]
+struct JumpTableData {
+    width: usize,
+    height: usize,
+    fingerprint: u64,
+    distances: Vec<[i32; 8]>,
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<JumpTableData> for JumpTable {
+    type Error = String;
+
+    fn try_from(data: JumpTableData) -> Result<JumpTable, String> {
+        let table = JumpTable {
+            width: data.width,
+            height: data.height,
+            fingerprint: data.fingerprint,
+            distances: data.distances,
+        };
+        if !table.is_consistent() {
+            return Err(String::from(
+                "the jump distances do not fit the size of the table",
+            ));
+        }
+        Ok(table)
+    }
+}
+
+/// Find a shortest path between `start` and `goal` with JPS+.
+///
+/// Same as `jps`, but the jumps are read from the precomputed `table`. Use a
+/// `JpsPlus` to run many queries on the same map.
+///
+/// # Panics
+///
+/// Panics if `table` does not match `map` (see `JumpTable::matches`).
+///
+/// # Examples
+///
+/// ```
+/// use std::path::Path;
+/// use movingai::parser::parse_map_file;
+/// use movingai::search::{JumpTable, jps_plus};
+///
+/// let map = parse_map_file(Path::new("./tests/arena.map")).unwrap();
+/// let table = JumpTable::new(&map);
+/// let result = jps_plus(&map, &table, (1, 11), (1, 14)).unwrap();
+/// assert_eq!(result.path, vec![(1, 11), (1, 12), (1, 13), (1, 14)]);
+/// assert_eq!(result.cost, 3.0);
+/// ```
+pub fn jps_plus<R: TraversalRules>(
+    map: &MovingAiMap<R>,
+    table: &JumpTable,
+    start: Coords2D,
+    goal: Coords2D,
+) -> Option<SearchResult> {
+    JpsPlus::new(map, table).search(start, goal)
+}
+
+/// A reusable JPS+ search on a `MovingAiMap`.
+///
+/// JPS+ (Rabin, 2015) explores the same jump points of `JumpPointSearch`,
+/// but reads the jumps from a `JumpTable` instead of scanning the map. Like
+/// `JumpPointSearch`, it falls back on A* on the maps where jumps do not
+/// follow `is_traversable_from`.
+///
+/// # Examples
+///
+/// ```
+/// use std::path::Path;
+/// use movingai::parser::{parse_map_file, parse_scen_file};
+/// use movingai::search::{JpsPlus, JumpTable};
+///
+/// let map = parse_map_file(Path::new("./tests/arena.map")).unwrap();
+/// let scen = parse_scen_file(Path::new("./tests/arena.map.scen")).unwrap();
+/// let table = JumpTable::new(&map);
+/// let mut search = JpsPlus::new(&map, &table);
+/// for record in &scen {
+///     let result = search.search(record.start_pos, record.goal_pos).unwrap();
+///     assert!((result.cost - record.optimal_length).abs() < 0.0001);
+/// }
+/// ```
+#[derive(Debug)]
+pub struct JpsPlus<'a, R> {
+    map: &'a MovingAiMap<R>,
+    table: &'a JumpTable,
+    uses_jumps: bool,
+    nodes: NodeTable,
+    open: BinaryHeap<Node>,
+    fallback: AStar,
+}
+
+impl<'a, R: TraversalRules> JpsPlus<'a, R> {
+    /// Prepare a JPS+ search on `map` using the jumps in `table`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `table` does not match `map` (see `JumpTable::matches`).
+    pub fn new(map: &'a MovingAiMap<R>, table: &'a JumpTable) -> JpsPlus<'a, R> {
+        assert!(table.matches(map), "the jump table does not match the map");
+        JpsPlus {
+            map,
+            table,
+            uses_jumps: supports_jumps(map),
+            nodes: NodeTable::default(),
+            open: BinaryHeap::new(),
+            fallback: AStar::new(),
+        }
+    }
+
+    /// Check if the search uses jumps on this map, instead of falling back
+    /// on A*.
+    pub fn uses_jumps(&self) -> bool {
+        self.uses_jumps
+    }
+
+    /// Same as `jps_plus`, reusing the memory of this `JpsPlus`.
+    pub fn search(&mut self, start: Coords2D, goal: Coords2D) -> Option<SearchResult> {
+        if !self.uses_jumps {
+            return self.fallback.search(self.map, start, goal);
+        }
+        if self.map.is_out_of_bound(start) || self.map.is_out_of_bound(goal) {
+            return None;
+        }
+        if !self.map.is_traversable(start) || !self.map.is_traversable(goal) {
+            return None;
+        }
+        if start == goal {
+            return Some(SearchResult {
+                path: vec![start],
+                cost: 0.0,
+                expanded: 0,
+            });
+        }
+
+        let width = self.map.width();
+        let index = |(x, y): Coords2D| y * width + x;
+        self.nodes.reset(width * self.map.height());
+        self.open.clear();
+        let mut expanded = 0;
+
+        self.nodes.update(index(start), 0.0, start);
+        self.open.push(Node {
+            f: octile(start, goal),
+            g: 0.0,
+            coords: start,
+        });
+
+        while let Some(Node {
+            g: cost, coords, ..
+        }) = self.open.pop()
+        {
+            let current = index(coords);
+            if self.nodes.is_closed(current) {
+                continue;
+            }
+            if coords == goal {
+                let jump_points = self.nodes.path(width, goal);
+                return Some(SearchResult {
+                    path: fill_path(&jump_points),
+                    cost,
+                    expanded,
+                });
+            }
+            self.nodes.close(current);
+            expanded += 1;
+            let parent = self.nodes.parent(current);
+            let (x, y) = (coords.0 as isize, coords.1 as isize);
+            let walk = |dx, dy| {
+                let (nx, ny) = (x + dx, y + dy);
+                nx >= 0 && ny >= 0 && self.map.is_traversable((nx as usize, ny as usize))
+            };
+            for direction in pruned_directions(coords, parent, walk) {
+                let Some(target) = self.successor(coords, direction, goal) else {
+                    continue;
+                };
+                let next = index(target);
+                let tentative = cost + octile(coords, target);
+                if !self.nodes.is_closed(next) && tentative < self.nodes.g(next) {
+                    self.nodes.update(next, tentative, coords);
+                    self.open.push(Node {
+                        f: tentative + octile(target, goal),
+                        g: tentative,
+                        coords: target,
+                    });
+                }
+            }
+        }
+
+        None
+    }
+
+    /// The node reached from `coords` jumping towards `direction`: the goal,
+    /// the tile where a diagonal jump must turn towards the goal, or the
+    /// jump point stored in the table.
+    fn successor(
+        &self,
+        coords: Coords2D,
+        direction: (isize, isize),
+        goal: Coords2D,
+    ) -> Option<Coords2D> {
+        let (dx, dy) = direction;
+        let distance = self.table.distance(coords, direction);
+        let reach = distance.unsigned_abs() as usize;
+        let rx = goal.0 as isize - coords.0 as isize;
+        let ry = goal.1 as isize - coords.1 as isize;
+        let steps = if dx != 0 && dy != 0 {
+            let towards_goal = rx.signum() == dx && ry.signum() == dy;
+            let turn = rx.unsigned_abs().min(ry.unsigned_abs());
+            if towards_goal && turn <= reach {
+                turn
+            } else if distance > 0 {
+                reach
+            } else {
+                return None;
+            }
+        } else {
+            let on_line = if dx != 0 {
+                ry == 0 && rx.signum() == dx
+            } else {
+                rx == 0 && ry.signum() == dy
+            };
+            let to_goal = rx.unsigned_abs().max(ry.unsigned_abs());
+            if on_line && to_goal <= reach {
+                to_goal
+            } else if distance > 0 {
+                reach
+            } else {
+                return None;
+            }
+        };
+        let steps = steps as isize;
+        Some((
+            (coords.0 as isize + dx * steps) as usize,
+            (coords.1 as isize + dy * steps) as usize,
+        ))
+    }
+}
+
+/// The index in `DIRECTIONS` of a straight direction.
+fn straight(dx: isize, dy: isize) -> usize {
+    match (dx, dy) {
+        (1, 0) => 0,
+        (-1, 0) => 1,
+        (0, 1) => 2,
+        _ => 3,
+    }
+}
+
+/// The indices from `0` to `size`, ordered so that the index following each
+/// one along `delta` comes first.
+fn ordered(size: usize, delta: isize) -> Box<dyn Iterator<Item = usize>> {
+    if delta > 0 {
+        Box::new((0..size).rev())
+    } else {
+        Box::new(0..size)
+    }
+}
+
+/// A FNV-1a hash of the size and the traversable tiles of a map.
+fn fingerprint(width: usize, height: usize, walkable: &[bool]) -> u64 {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    let bytes = (width as u64)
+        .to_le_bytes()
+        .into_iter()
+        .chain((height as u64).to_le_bytes())
+        .chain(walkable.iter().map(|&w| w as u8));
+    for byte in bytes {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(0x0100_0000_01b3);
+    }
+    hash
+}
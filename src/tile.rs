@@ -0,0 +1,106 @@
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// A tile of a MovingAI map.
+///
+/// Every variant corresponds to one of the characters of the MovingAI
+/// format. Characters that are not part of the format are kept as
+/// `Tile::Unknown`.
+///
+/// # Examples
+///
+/// ```
+/// use movingai::Tile;
+///
+/// let tile = Tile::from('W');
+/// assert_eq!(tile, Tile::Water);
+/// assert!(tile.is_passable());
+/// assert_eq!(char::from(tile), 'W');
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Tile {
+    /// Passable terrain (`.`).
+    Free,
+    /// Passable terrain (`G`).
+    Ground,
+    /// Out of bounds (`@`).
+    OutOfBounds,
+    /// Out of bounds (`O`).
+    Obstacle,
+    /// Trees, unpassable (`T`).
+    Tree,
+    /// Swamp, passable from regular terrain (`S`).
+    Swamp,
+    /// Water, traversable but not passable from terrain (`W`).
+    Water,
+    /// A character that is not part of the MovingAI format.
+    Unknown(char),
+}
+
+impl Tile {
+    /// Check if the tile can be traversed **in some situation**.
+    ///
+    /// This is `true` for `.`, `G`, `S` and `W`. See
+    /// `Map2D::is_traversable` for the details.
+    pub fn is_passable(self) -> bool {
+        matches!(self, Tile::Free | Tile::Ground | Tile::Swamp | Tile::Water)
+    }
+
+    /// Check if the tile is regular terrain (`.` or `G`).
+    pub fn is_terrain(self) -> bool {
+        matches!(self, Tile::Free | Tile::Ground)
+    }
+
+    /// Check if the tile is an obstacle (`@`, `O` or `T`).
+    ///
+    /// Unknown tiles are neither passable nor obstacles.
+    pub fn is_obstacle(self) -> bool {
+        matches!(self, Tile::OutOfBounds | Tile::Obstacle | Tile::Tree)
+    }
+
+    /// Check if the tile is water (`W`).
+    pub fn is_water(self) -> bool {
+        self == Tile::Water
+    }
+
+    /// Check if the tile is swamp (`S`).
+    pub fn is_swamp(self) -> bool {
+        self == Tile::Swamp
+    }
+
+    /// Check if the tile is part of the MovingAI format.
+    pub fn is_known(self) -> bool {
+        !matches!(self, Tile::Unknown(_))
+    }
+}
+
+impl From<char> for Tile {
+    fn from(c: char) -> Self {
+        match c {
+            '.' => Tile::Free,
+            'G' => Tile::Ground,
+            '@' => Tile::OutOfBounds,
+            'O' => Tile::Obstacle,
+            'T' => Tile::Tree,
+            'S' => Tile::Swamp,
+            'W' => Tile::Water,
+            _ => Tile::Unknown(c),
+        }
+    }
+}
+
+impl From<Tile> for char {
+    fn from(tile: Tile) -> Self {
+        match tile {
+            Tile::Free => '.',
+            Tile::Ground => 'G',
+            Tile::OutOfBounds => '@',
+            Tile::Obstacle => 'O',
+            Tile::Tree => 'T',
+            Tile::Swamp => 'S',
+            Tile::Water => 'W',
+            Tile::Unknown(c) => c,
+        }
+    }
+}
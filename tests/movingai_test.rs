@@ -1,10 +1,57 @@
+use std::fs;
 use std::path::Path;
+use std::sync::Arc;
 
+use movingai::bench::run_scenarios;
+use movingai::bench::run_scenarios_with_options;
+use movingai::bench::BenchmarkOptions;
+use movingai::components::label_components;
+use movingai::dataset::Dataset;
+use movingai::dataset::DatasetError;
+use movingai::heuristics;
+use movingai::parser::parse_map;
 use movingai::parser::parse_map_file;
+use movingai::parser::parse_map_reader;
+use movingai::parser::parse_map_with_options;
 use movingai::parser::parse_scen;
 use movingai::parser::parse_scen_file;
+use movingai::parser::write_map;
+use movingai::parser::write_scen;
+use movingai::parser::ParseOptions;
+use movingai::parser::ScenReader;
+use movingai::parser::SCEN_PRECISION;
+use movingai::render::render;
+use movingai::render::render_window;
+use movingai::render::write_pgm;
+use movingai::render::write_ppm;
+use movingai::render::write_ppm_file;
+use movingai::render::ImageOptions;
+use movingai::render::Overlay;
+use movingai::render::Palette;
+use movingai::render::PathStyle;
+use movingai::render::Window;
+use movingai::scenario::generate_scenario;
+use movingai::scenario::verify_scenario;
+use movingai::scenario::Endpoint;
+use movingai::scenario::GeneratorOptions;
+use movingai::scenario::ScenarioIssue;
+use movingai::search::astar;
+use movingai::search::astar_with_heuristic;
+use movingai::search::dijkstra;
+use movingai::search::jps;
+use movingai::search::AStar;
+use movingai::search::JpsPlus;
+use movingai::search::JumpPointSearch;
+use movingai::search::JumpTable;
+use movingai::DiagonalPolicy;
 use movingai::Map2D;
+use movingai::MapType;
 use movingai::MovingAiMap;
+use movingai::ParseError;
+use movingai::PathError;
+use movingai::Tile;
+use movingai::TraversalRules;
+use movingai::UniformRules;
 
 #[test]
 fn indexing() {
@@ -112,3 +159,1220 @@ fn parse_scen_empty_line_is_skipped() {
     assert!(result.is_ok());
     assert_eq!(result.unwrap().len(), 1);
 }
+
+#[test]
+fn parse_map_missing_header_reports_location() {
+    let result = parse_map("type octile\nwidth 2\nmap\n..\n..");
+    match result {
+        Err(ParseError::MissingHeader { key, line, column }) => {
+            assert_eq!(key, "height");
+            assert_eq!((line, column), (3, 1));
+        }
+        other => panic!("unexpected result: {:?}", other),
+    }
+}
+
+#[test]
+fn parse_map_invalid_number_reports_location() {
+    let err = parse_map("type octile\nheight  x2\nwidth 2\nmap\n..\n..").unwrap_err();
+    assert!(matches!(
+        err,
+        ParseError::InvalidNumber { field: "height", line: 2, column: 9, ref text } if text == "x2"
+    ));
+}
+
+#[test]
+fn parse_map_huge_declared_size() {
+    // The declared size is never allocated upfront.
+    let err = parse_map("type octile\nheight 1000000\nwidth 1000000\nmap\n..\n").unwrap_err();
+    assert!(matches!(
+        err,
+        ParseError::ShortRow {
+            row: 0,
+            line: 5,
+            ..
+        }
+    ));
+    let huge = format!("type octile\nheight {0}\nwidth {0}\nmap\n..\n", usize::MAX);
+    assert!(matches!(parse_map(&huge), Err(ParseError::InvalidMapSize)));
+    assert!(matches!(
+        MovingAiMap::new(String::from("octile"), usize::MAX, 2, vec![]),
+        Err(ParseError::InvalidMapSize)
+    ));
+}
+
+#[test]
+fn parse_map_unknown_tile_and_short_row() {
+    let err = parse_map_with_options(
+        "type octile\nheight 2\nwidth 3\nmap\n...\n.?.",
+        &ParseOptions::strict(),
+    )
+    .unwrap_err();
+    assert!(matches!(
+        err,
+        ParseError::UnknownTile {
+            line: 6,
+            column: 2,
+            tile: '?'
+        }
+    ));
+    let err = parse_map("type octile\nheight 2\nwidth 3\nmap\n..\n....").unwrap_err();
+    assert!(matches!(
+        err,
+        ParseError::ShortRow {
+            line: 5,
+            column: 3,
+            expected: 3,
+            ..
+        }
+    ));
+}
+
+#[test]
+fn parse_scen_errors_report_location() {
+    let err = parse_scen("version 1\n0\tarena.map\t49\t49\t1\tone\t1\t12\t1").unwrap_err();
+    assert!(matches!(
+        err,
+        ParseError::InvalidNumber { field: "start y", line: 2, column: 21, ref text } if text == "one"
+    ));
+    assert_eq!((err.line(), err.column()), (Some(2), Some(21)));
+    let err = parse_scen("version 1\n0\tmaps/dao/arena.map\t49").unwrap_err();
+    assert!(matches!(
+        err,
+        ParseError::WrongFieldCount {
+            line: 2,
+            expected: 9,
+            found: 3,
+            ..
+        }
+    ));
+    let err = parse_scen("0\ta\t1\t1\t0\t0\t0\t0\t0\textra").unwrap_err();
+    assert!(matches!(
+        err,
+        ParseError::WrongFieldCount {
+            line: 1,
+            column: 19,
+            expected: 9,
+            found: 10,
+            ..
+        }
+    ));
+}
+
+#[test]
+fn parse_scen_ignores_trailing_whitespace() {
+    let scen = parse_scen("version 1\n0\tarena.map\t49\t49\t1\t11\t1\t12\t1\t\n").unwrap();
+    assert_eq!(scen[0].optimal_length, 1.0);
+    let scen = parse_scen("0\tarena.map\t49\t49\t1\t11\t1\t12\t1.5 \r\n").unwrap();
+    assert_eq!(scen[0].optimal_length, 1.5);
+}
+
+#[test]
+fn write_map_round_trip() {
+    let map = parse_map_file(Path::new("./tests/arena.map")).unwrap();
+    let contents = write_map(&map);
+    assert_eq!(parse_map(&contents).unwrap(), map);
+
+    let four = MovingAiMap::new(String::from("test"), 2, 2, vec!['.', 'T', 'W', 'S']).unwrap();
+    assert_eq!(parse_map(&write_map(&four)).unwrap(), four);
+
+    // Maps with a zero dimension can be neither built nor parsed.
+    for (height, width) in [(0, 0), (3, 0), (0, 3)] {
+        assert!(matches!(
+            MovingAiMap::new(String::from("octile"), height, width, vec![]),
+            Err(ParseError::InvalidMapSize)
+        ));
+    }
+    assert!(matches!(
+        parse_map("type octile\nheight 3\nwidth 0\nmap\n"),
+        Err(ParseError::InvalidNumber { field: "width", .. })
+    ));
+    for (height, width) in [(1, 1), (1, 5), (5, 1)] {
+        let tiles = vec!['.'; height * width];
+        let thin = MovingAiMap::new(String::from("octile"), height, width, tiles).unwrap();
+        assert_eq!(parse_map(&write_map(&thin)).unwrap(), thin);
+    }
+
+    // Line breaks cannot be written as tiles.
+    for tile in ['\n', '\r'] {
+        assert!(matches!(
+            MovingAiMap::new(String::from("octile"), 1, 2, vec!['.', tile]),
+            Err(ParseError::UnsupportedTile { coords: (1, 0), .. })
+        ));
+    }
+
+    // The diagonal policy is not part of the format.
+    let map = parse_map_file(Path::new("./tests/arena.map"))
+        .unwrap()
+        .with_diagonal_policy(DiagonalPolicy::OneSideFree);
+    let parsed = parse_map(&write_map(&map)).unwrap();
+    assert_eq!(parsed.diagonal_policy(), DiagonalPolicy::default());
+    assert_eq!(
+        parsed.with_diagonal_policy(DiagonalPolicy::OneSideFree),
+        map
+    );
+}
+
+#[test]
+fn write_scen_is_byte_compatible() {
+    let contents = std::fs::read_to_string("./tests/maze512-32-9.map.scen").unwrap();
+    let scen = parse_scen(&contents).unwrap();
+    assert_eq!(write_scen(&scen, SCEN_PRECISION), contents);
+    assert!(write_scen(&scen[..1], 2).ends_with("\t3.41\n"));
+}
+
+#[test]
+fn strict_and_lenient_parsing() {
+    let contents = "type triangle\nheight 2\nwidth 3\nversion 2\nmap\n.X.\n....\nextra";
+    let (map, warnings) = parse_map_with_options(contents, &ParseOptions::lenient()).unwrap();
+    assert_eq!(map.width(), 3);
+    assert_eq!(*map.get((1, 0)), 'X');
+    assert_eq!(*map.get((2, 1)), '.');
+    assert!(matches!(
+        warnings[0],
+        ParseError::UnknownMapType {
+            line: 1,
+            column: 6,
+            ..
+        }
+    ));
+    assert!(matches!(
+        warnings[1],
+        ParseError::UnknownHeader { line: 4, .. }
+    ));
+    assert!(matches!(
+        warnings[2],
+        ParseError::UnknownTile {
+            line: 6,
+            column: 2,
+            tile: 'X'
+        }
+    ));
+    assert!(matches!(
+        warnings[3],
+        ParseError::LongRow {
+            line: 7,
+            column: 4,
+            expected: 3,
+            ..
+        }
+    ));
+    assert!(matches!(
+        warnings[4],
+        ParseError::TrailingData { line: 8, .. }
+    ));
+    assert_eq!(warnings.len(), 5);
+
+    let err = parse_map_with_options(contents, &ParseOptions::strict()).unwrap_err();
+    assert!(matches!(err, ParseError::UnknownMapType { line: 1, .. }));
+
+    let (_, warnings) = parse_map_with_options(
+        &std::fs::read_to_string("./tests/arena.map").unwrap(),
+        &ParseOptions::strict(),
+    )
+    .unwrap();
+    assert!(warnings.is_empty());
+}
+
+#[test]
+fn row_lengths_are_checked_row_by_row() {
+    // One short and one long row: the total number of tiles is right.
+    let err = parse_map("type octile\nheight 2\nwidth 2\nmap\n...\n.").unwrap_err();
+    assert!(matches!(
+        err,
+        ParseError::ShortRow {
+            row: 1,
+            line: 6,
+            found: 1,
+            ..
+        }
+    ));
+    let err = parse_map_with_options(
+        "type octile\nheight 2\nwidth 2\nmap\n...\n.",
+        &ParseOptions::strict(),
+    )
+    .unwrap_err();
+    assert!(matches!(
+        err,
+        ParseError::LongRow {
+            row: 0,
+            line: 5,
+            column: 3,
+            found: 3,
+            ..
+        }
+    ));
+    let err = parse_map("type octile\nheight 3\nwidth 2\nmap\n..\n..\n").unwrap_err();
+    assert!(matches!(
+        err,
+        ParseError::MissingRows {
+            line: 7,
+            expected: 3,
+            found: 2,
+            ..
+        }
+    ));
+}
+
+#[test]
+fn windows_line_endings_and_trailing_whitespace() {
+    let contents = "type octile\r\nheight 2\r\nwidth 2\r\nmap\r\n.T \r\n..\t\r\n";
+    let mut options = ParseOptions::strict();
+    assert!(matches!(
+        parse_map_with_options(contents, &options),
+        Err(ParseError::LongRow { row: 0, .. })
+    ));
+    options.trim_trailing_whitespace = true;
+    let (map, _) = parse_map_with_options(contents, &options).unwrap();
+    assert_eq!(*map.get((1, 0)), 'T');
+    options.accept_crlf = false;
+    assert!(parse_map_with_options(contents, &options).is_err());
+}
+
+#[test]
+fn streaming_parsers() {
+    let file = std::fs::File::open("./tests/arena.map").unwrap();
+    let map = parse_map_reader(std::io::BufReader::new(file)).unwrap();
+    assert_eq!(map, parse_map_file(Path::new("./tests/arena.map")).unwrap());
+
+    // Two concatenated scenario files, each one with its own header.
+    let first = std::fs::read("./tests/arena.map.scen").unwrap();
+    let second = std::fs::read("./tests/arena2.map.scen").unwrap();
+    let reader = std::io::Read::chain(&first[..], &second[..]);
+    let records: Vec<_> = ScenReader::new(reader).map(Result::unwrap).collect();
+    assert_eq!(records.len(), 160 + 929);
+    assert_eq!(records[160 + 3].start_pos, (102, 165));
+
+    let mut reader = ScenReader::new("0\ta.map\t1\n0\ta.map\t1\t1\t0\t0\t0\t0\t0".as_bytes());
+    assert!(matches!(
+        reader.next(),
+        Some(Err(ParseError::WrongFieldCount { line: 1, .. }))
+    ));
+    assert!(reader.next().unwrap().is_ok());
+    assert!(reader.next().is_none());
+}
+
+#[test]
+fn tiles_must_be_one_byte_characters() {
+    let err = MovingAiMap::new(String::from("octile"), 1, 2, vec!['.', '█']).unwrap_err();
+    assert!(matches!(
+        err,
+        ParseError::UnsupportedTile {
+            coords: (1, 0),
+            tile: '█',
+            line: None,
+            column: None,
+        }
+    ));
+    // Even in lenient mode, where unknown tiles are only warnings.
+    let err = parse_map("type octile\nheight 2\nwidth 2\nmap\n..\n.█").unwrap_err();
+    assert!(matches!(
+        err,
+        ParseError::UnsupportedTile {
+            coords: (1, 1),
+            tile: '█',
+            line: Some(6),
+            column: Some(2),
+        }
+    ));
+    assert_eq!((err.line(), err.column()), (Some(6), Some(2)));
+}
+
+#[test]
+fn tiles() {
+    for c in ".G@OTSW".chars() {
+        let tile = Tile::from(c);
+        assert!(tile.is_known());
+        assert_eq!(char::from(tile), c);
+    }
+    assert_eq!(Tile::from('x'), Tile::Unknown('x'));
+    assert!(!Tile::Unknown('x').is_passable());
+    assert!(Tile::Swamp.is_swamp() && Tile::Swamp.is_passable());
+    assert!(Tile::Tree.is_obstacle());
+
+    let map = parse_map_file(Path::new("./tests/arena.map")).unwrap();
+    assert_eq!(map.tile((3, 0)), Tile::Tree);
+    assert_eq!(map.tile((5, 2)), Tile::Free);
+}
+
+#[test]
+fn custom_traversal_rules() {
+    struct CornerCutting;
+
+    impl TraversalRules for CornerCutting {
+        fn is_passable(&self, tile: Tile) -> bool {
+            tile.is_passable()
+        }
+
+        fn can_move(&self, _from: Tile, to: Tile) -> bool {
+            to.is_passable()
+        }
+
+        fn can_move_diagonally(
+            &self,
+            _policy: DiagonalPolicy,
+            _from: Tile,
+            to: Tile,
+            _a: Tile,
+            _b: Tile,
+        ) -> bool {
+            to.is_passable()
+        }
+    }
+
+    let map = MovingAiMap::new(String::from("octile"), 2, 2, vec!['.', 'T', 'W', '.']).unwrap();
+    assert!(!map.is_traversable_from((0, 0), (0, 1)));
+    assert!(!map.is_traversable_from((0, 0), (1, 1)));
+    assert_eq!(map.neighbors((0, 0)).len(), 0);
+
+    let uniform = map.clone().with_rules(UniformRules);
+    assert!(uniform.is_traversable_from((0, 0), (0, 1)));
+    assert!(!uniform.is_traversable_from((0, 0), (1, 1)));
+    assert_eq!(uniform.free_states(), map.free_states());
+
+    let cutting = map.with_rules(CornerCutting);
+    assert!(cutting.is_traversable_from((0, 0), (1, 1)));
+    assert_eq!(cutting.neighbors((0, 0)).len(), 2);
+}
+
+#[test]
+fn diagonal_policies() {
+    // .T.
+    // ...
+    // TT.
+    let map = MovingAiMap::new(
+        String::from("octile"),
+        3,
+        3,
+        vec!['.', 'T', '.', '.', '.', '.', 'T', 'T', '.'],
+    )
+    .unwrap();
+    assert_eq!(map.diagonal_policy(), DiagonalPolicy::NoCornerCutting);
+    assert!(!map.is_traversable_from((1, 1), (0, 0)));
+    assert!(!map.is_traversable_from((1, 1), (2, 2)));
+    assert_eq!(map.neighbors((1, 1)).len(), 2);
+
+    let map = map.with_diagonal_policy(DiagonalPolicy::OneSideFree);
+    assert!(map.is_traversable_from((1, 1), (0, 0)));
+    assert!(!map.is_traversable_from((0, 1), (1, 2)));
+    assert_eq!(map.neighbors((1, 1)).len(), 5);
+
+    // .T
+    // T.
+    let map = MovingAiMap::new(String::from("octile"), 2, 2, vec!['.', 'T', 'T', '.']).unwrap();
+    assert!(!map.is_traversable_from((0, 0), (1, 1)));
+    let map = map.with_diagonal_policy(DiagonalPolicy::OneSideFree);
+    assert!(!map.is_traversable_from((0, 0), (1, 1)));
+    let map = map.with_diagonal_policy(DiagonalPolicy::AlwaysAllow);
+    assert!(map.is_traversable_from((0, 0), (1, 1)));
+    assert_eq!(map.neighbors((1, 1)).as_slice(), &[(0, 0)]);
+}
+
+#[test]
+fn astar_matches_optimal_lengths() {
+    let map = parse_map_file(Path::new("./tests/arena.map")).unwrap();
+    let scen = parse_scen_file(Path::new("./tests/arena.map.scen")).unwrap();
+    for record in scen {
+        let result = astar(&map, record.start_pos, record.goal_pos).unwrap();
+        assert!((result.cost - record.optimal_length).abs() < 0.0001);
+        assert_eq!(result.path.first(), Some(&record.start_pos));
+        assert_eq!(result.path.last(), Some(&record.goal_pos));
+        for step in result.path.windows(2) {
+            assert!(map.is_traversable_from(step[0], step[1]));
+        }
+    }
+
+    let map = parse_map_file(Path::new("./tests/maze512-32-9.map")).unwrap();
+    let scen = parse_scen_file(Path::new("./tests/maze512-32-9.map.scen")).unwrap();
+    for record in scen.iter().step_by(1000) {
+        let result = astar(&map, record.start_pos, record.goal_pos).unwrap();
+        assert!((result.cost - record.optimal_length).abs() < 0.0001);
+    }
+}
+
+#[test]
+fn astar_unreachable_goal() {
+    let map = MovingAiMap::new(String::from("octile"), 1, 3, vec!['.', 'T', '.']).unwrap();
+    assert!(astar(&map, (0, 0), (2, 0)).is_none());
+    assert!(astar(&map, (0, 0), (5, 0)).is_none());
+    let result = astar(&map, (0, 0), (0, 0)).unwrap();
+    assert_eq!((result.path, result.cost), (vec![(0, 0)], 0.0));
+
+    // Searches cannot start from a blocked tile.
+    let map = MovingAiMap::new(String::from("octile"), 1, 2, vec!['@', '.']).unwrap();
+    assert!(astar(&map, (0, 0), (1, 0)).is_none());
+    assert!(astar(&map, (0, 0), (0, 0)).is_none());
+    let distances = dijkstra(&map, (0, 0), true);
+    assert!(distances.distance((0, 0)).is_infinite());
+    assert!(distances.distance((1, 0)).is_infinite());
+}
+
+#[test]
+fn dijkstra_distance_map() {
+    let map = parse_map_file(Path::new("./tests/arena.map")).unwrap();
+    let scen = parse_scen_file(Path::new("./tests/arena.map.scen")).unwrap();
+    let source = scen[0].start_pos;
+    let distances = dijkstra(&map, source, true);
+    assert_eq!(distances.distance(source), 0.0);
+    assert_eq!(distances.distances().len(), 49 * 49);
+    for record in scen.iter().filter(|r| r.start_pos == source) {
+        let distance = distances.distance(record.goal_pos);
+        assert!((distance - record.optimal_length).abs() < 0.0001);
+        let path = distances.path_to(record.goal_pos).unwrap();
+        assert_eq!(path.first(), Some(&source));
+        assert_eq!(path.last(), Some(&record.goal_pos));
+    }
+    let reachable = distances
+        .distances()
+        .iter()
+        .filter(|d| d.is_finite())
+        .count();
+    assert_eq!(reachable, map.free_states());
+    assert!(distances.distance((0, 0)).is_infinite());
+    assert_eq!(distances.predecessor((0, 0)), None);
+
+    let distances = dijkstra(&map, source, false);
+    assert!(!distances.has_predecessors());
+    assert_eq!(distances.path_to(scen[0].goal_pos), None);
+}
+
+#[test]
+fn jps_matches_optimal_lengths() {
+    for (map_path, scen_path) in [
+        ("./tests/arena.map", "./tests/arena.map.scen"),
+        ("./tests/maze512-32-9.map", "./tests/maze512-32-9.map.scen"),
+    ] {
+        let map = parse_map_file(Path::new(map_path)).unwrap();
+        let scen = parse_scen_file(Path::new(scen_path)).unwrap();
+        let mut search = JumpPointSearch::new(&map);
+        assert!(search.uses_jumps());
+        for record in scen.iter().step_by(50) {
+            let result = search.search(record.start_pos, record.goal_pos).unwrap();
+            assert!((result.cost - record.optimal_length).abs() < 0.0001);
+            assert_eq!(result.path.first(), Some(&record.start_pos));
+            assert_eq!(result.path.last(), Some(&record.goal_pos));
+            for step in result.path.windows(2) {
+                assert!(map.is_traversable_from(step[0], step[1]));
+            }
+        }
+    }
+}
+
+/// A square octile map with scattered obstacles, to exercise forced
+/// neighbours.
+fn scattered_map(size: usize) -> MovingAiMap {
+    let tiles = (0..size * size)
+        .map(|i: usize| {
+            if (i.wrapping_mul(2654435761) >> 13).is_multiple_of(4) {
+                'T'
+            } else {
+                '.'
+            }
+        })
+        .collect();
+    MovingAiMap::new(String::from("octile"), size, size, tiles).unwrap()
+}
+
+#[test]
+fn jps_matches_astar() {
+    let map = scattered_map(40);
+    let mut search = JumpPointSearch::new(&map);
+    for start in map.coords().step_by(37) {
+        for goal in map.coords().step_by(89) {
+            let expected = astar(&map, start, goal).map(|r| r.cost);
+            let found = search.search(start, goal).map(|r| r.cost);
+            match (expected, found) {
+                (Some(a), Some(b)) => assert!((a - b).abs() < 0.0001),
+                (None, None) => {}
+                _ => panic!("{:?} -> {:?}: {:?} vs {:?}", start, goal, expected, found),
+            }
+        }
+    }
+}
+
+#[test]
+fn jps_falls_back_on_astar() {
+    let tiles = vec!['.', '.', 'W', '.', 'W', 'W', '.', '.', '.'];
+    let map = MovingAiMap::new(String::from("octile"), 3, 3, tiles.clone()).unwrap();
+    assert!(!JumpPointSearch::new(&map).uses_jumps());
+    assert_eq!(jps(&map, (0, 0), (1, 2)), astar(&map, (0, 0), (1, 2)));
+
+    let map = MovingAiMap::new(String::from("four-connected"), 3, 3, vec!['.'; 9]).unwrap();
+    assert!(!JumpPointSearch::new(&map).uses_jumps());
+    let map = MovingAiMap::new(String::from("octile"), 3, 3, tiles)
+        .unwrap()
+        .with_rules(UniformRules);
+    assert!(JumpPointSearch::new(&map).uses_jumps());
+    let map = map.with_diagonal_policy(DiagonalPolicy::AlwaysAllow);
+    assert!(!JumpPointSearch::new(&map).uses_jumps());
+}
+
+#[test]
+fn jps_plus_matches_jps() {
+    let map = parse_map_file(Path::new("./tests/maze512-32-9.map")).unwrap();
+    let scen = parse_scen_file(Path::new("./tests/maze512-32-9.map.scen")).unwrap();
+    let table = JumpTable::new(&map);
+    assert!(table.matches(&map));
+    let mut search = JpsPlus::new(&map, &table);
+    assert!(search.uses_jumps());
+    for record in scen.iter().step_by(50) {
+        let result = search.search(record.start_pos, record.goal_pos).unwrap();
+        assert!((result.cost - record.optimal_length).abs() < 0.0001);
+        assert_eq!(result.path.first(), Some(&record.start_pos));
+        assert_eq!(result.path.last(), Some(&record.goal_pos));
+        for step in result.path.windows(2) {
+            assert!(map.is_traversable_from(step[0], step[1]));
+        }
+    }
+
+    let map = scattered_map(40);
+    let table = JumpTable::new(&map);
+    let mut plus = JpsPlus::new(&map, &table);
+    let mut online = JumpPointSearch::new(&map);
+    for start in map.coords().step_by(37) {
+        for goal in map.coords().step_by(89) {
+            let expected = online.search(start, goal).map(|r| r.cost);
+            let found = plus.search(start, goal).map(|r| r.cost);
+            match (expected, found) {
+                (Some(a), Some(b)) => assert!((a - b).abs() < 0.0001),
+                (None, None) => {}
+                _ => panic!("{:?} -> {:?}: {:?} vs {:?}", start, goal, expected, found),
+            }
+        }
+    }
+}
+
+#[test]
+fn jump_table_matches_its_map() {
+    let map = parse_map_file(Path::new("./tests/arena.map")).unwrap();
+    let table = JumpTable::new(&map);
+    assert_eq!((table.width(), table.height()), (49, 49));
+    let other = MovingAiMap::new(String::from("octile"), 49, 49, vec!['.'; 49 * 49]).unwrap();
+    assert!(!table.matches(&other));
+    let other = MovingAiMap::new(String::from("octile"), 2, 2, vec!['.'; 4]).unwrap();
+    assert!(!table.matches(&other));
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn jump_table_serde_round_trip() {
+    let map = parse_map_file(Path::new("./tests/arena.map")).unwrap();
+    let table = JumpTable::new(&map);
+    let json = serde_json::to_string(&table).unwrap();
+    let loaded: JumpTable = serde_json::from_str(&json).unwrap();
+    assert_eq!(loaded, table);
+    assert!(loaded.matches(&map));
+    let result = movingai::search::jps_plus(&map, &loaded, (1, 11), (1, 14)).unwrap();
+    assert_eq!(result.cost, 3.0);
+
+    // Corrupted tables are rejected when they are loaded.
+    let mut value: serde_json::Value = serde_json::from_str(&json).unwrap();
+    value["distances"].as_array_mut().unwrap().pop();
+    assert!(serde_json::from_value::<JumpTable>(value).is_err());
+    let mut value: serde_json::Value = serde_json::from_str(&json).unwrap();
+    value["distances"][0][0] = serde_json::json!(1000);
+    assert!(serde_json::from_value::<JumpTable>(value).is_err());
+}
+
+#[test]
+fn bench_reports_times_and_failures() {
+    let map = parse_map_file(Path::new("./tests/arena.map")).unwrap();
+    let scen = parse_scen_file(Path::new("./tests/arena.map.scen")).unwrap();
+    let mut search = AStar::new();
+    let report = run_scenarios(&map, &scen, |map, start, goal| {
+        search.search(map, start, goal).map(|result| result.cost)
+    });
+    assert!(report.is_success());
+    assert_eq!(report.queries.len(), scen.len());
+    let stats = report.time_stats();
+    assert!(stats.median <= stats.p99 && stats.p99 <= stats.max);
+    assert!(stats.mean <= stats.max);
+    let buckets = report.buckets();
+    assert_eq!(buckets.iter().map(|b| b.queries).sum::<usize>(), scen.len());
+    assert!(buckets.windows(2).all(|w| w[0].bucket < w[1].bucket));
+
+    // A solver always one unit off, and failing on the first record.
+    let report = run_scenarios(&map, &scen, |map, start, goal| {
+        if start == scen[0].start_pos && goal == scen[0].goal_pos {
+            return None;
+        }
+        astar(map, start, goal).map(|result| result.cost + 1.0)
+    });
+    assert_eq!(report.failures().count(), scen.len());
+    assert_eq!(report.queries[0].found, None);
+    assert_eq!(buckets[0].failures, 0);
+    assert_eq!(report.buckets()[0].failures, report.buckets()[0].queries);
+
+    let options = BenchmarkOptions { epsilon: 1.5 };
+    let report = run_scenarios_with_options(&map, &scen, &options, |map, start, goal| {
+        astar(map, start, goal).map(|result| result.cost + 1.0)
+    });
+    assert!(report.is_success());
+}
+
+#[test]
+fn bench_report_to_csv() {
+    let map = parse_map_file(Path::new("./tests/arena.map")).unwrap();
+    let scen = parse_scen_file(Path::new("./tests/arena.map.scen")).unwrap();
+    let report = run_scenarios(&map, &scen, astar);
+    assert!(report.queries.iter().all(|q| q.expanded.is_some()));
+    let csv = report.to_csv();
+    let lines: Vec<&str> = csv.lines().collect();
+    assert_eq!(lines.len(), scen.len() + 1);
+    for (line, query) in lines[1..].iter().zip(&report.queries) {
+        let fields: Vec<&str> = line.split(',').collect();
+        assert_eq!(fields.len(), 11);
+        assert_eq!(fields[1].parse::<u32>().unwrap(), query.bucket);
+        assert_eq!(fields[4].parse::<usize>().unwrap(), query.goal.0);
+        assert_eq!(fields[7].parse::<f64>().unwrap(), query.found.unwrap());
+        assert_eq!(fields[8].parse::<usize>().unwrap(), query.expanded.unwrap());
+        assert_eq!(fields[9], "true");
+    }
+
+    // Without a path nor expanded nodes, the fields are left empty.
+    let report = run_scenarios(&map, &scen[..1], |_, _, _| None::<f64>);
+    let line = report.to_csv().lines().nth(1).unwrap().to_string();
+    assert!(line.contains(",,,false,"));
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn bench_report_to_json() {
+    let map = parse_map_file(Path::new("./tests/arena.map")).unwrap();
+    let scen = parse_scen_file(Path::new("./tests/arena.map.scen")).unwrap();
+    let report = run_scenarios(&map, &scen, astar);
+    let json = serde_json::to_value(&report).unwrap();
+    let query = &json["queries"][0];
+    assert_eq!(query["bucket"], scen[0].bucket);
+    assert_eq!(query["expanded"], report.queries[0].expanded.unwrap());
+    assert!(query["time"].is_f64());
+    let loaded: movingai::bench::BenchmarkReport = serde_json::from_value(json).unwrap();
+    assert_eq!(loaded.queries.len(), report.queries.len());
+    assert_eq!(loaded.queries[0].found, report.queries[0].found);
+}
+
+#[test]
+fn validate_paths() {
+    let map = parse_map_file(Path::new("./tests/arena.map")).unwrap();
+    let scen = parse_scen_file(Path::new("./tests/arena.map.scen")).unwrap();
+    for record in scen.iter().step_by(10) {
+        let result = astar(&map, record.start_pos, record.goal_pos).unwrap();
+        let cost = map.validate_path(&result.path).unwrap();
+        assert!((cost - record.optimal_length).abs() < 0.0001);
+    }
+
+    // . . W
+    // . T W
+    // . . .
+    let tiles = vec!['.', '.', 'W', '.', 'T', 'W', '.', '.', '.'];
+    let map = MovingAiMap::new(String::from("octile"), 3, 3, tiles).unwrap();
+    assert_eq!(map.validate_path(&[]), Err(PathError::Empty));
+    assert_eq!(map.validate_path(&[(0, 0)]), Ok(0.0));
+    assert_eq!(
+        map.validate_path(&[(0, 0), (0, 1), (0, 2), (1, 2)]),
+        Ok(3.0)
+    );
+    assert_eq!(
+        map.validate_path(&[(0, 0), (3, 0)]),
+        Err(PathError::OutOfBounds {
+            index: 1,
+            coords: (3, 0)
+        })
+    );
+    assert_eq!(
+        map.validate_path(&[(0, 0), (0, 1), (1, 1)]),
+        Err(PathError::Blocked {
+            index: 2,
+            coords: (1, 1),
+            tile: Tile::Tree
+        })
+    );
+    assert_eq!(
+        map.validate_path(&[(0, 0), (0, 2)]),
+        Err(PathError::NotAdjacent {
+            index: 1,
+            from: (0, 0),
+            to: (0, 2)
+        })
+    );
+    assert_eq!(
+        map.validate_path(&[(0, 0), (0, 0)]).unwrap_err().index(),
+        Some(1)
+    );
+    let error = map.validate_path(&[(0, 0), (1, 0), (2, 0)]).unwrap_err();
+    assert_eq!(
+        error,
+        PathError::IllegalTransition {
+            index: 2,
+            from: (1, 0),
+            to: (2, 0),
+            from_tile: Tile::Free,
+            to_tile: Tile::Water
+        }
+    );
+    assert_eq!(
+        error.to_string(),
+        "tile 2: cannot move from (1, 0) (`.`) to (2, 0) (`W`)"
+    );
+    let corner = [(0, 0), (0, 1), (1, 2)];
+    assert_eq!(
+        map.validate_path(&corner),
+        Err(PathError::CornerCut {
+            index: 2,
+            from: (0, 1),
+            to: (1, 2)
+        })
+    );
+    let map = map.with_diagonal_policy(DiagonalPolicy::AlwaysAllow);
+    assert_eq!(map.validate_path(&corner), Ok(1.0 + 2f64.sqrt()));
+
+    let map = MovingAiMap::new(String::from("four-connected"), 2, 2, vec!['.'; 4]).unwrap();
+    assert_eq!(
+        map.validate_path(&[(0, 0), (1, 1)]).unwrap_err().index(),
+        Some(1)
+    );
+}
+
+#[test]
+fn heuristics_are_admissible() {
+    let map = parse_map_file(Path::new("./tests/arena.map")).unwrap();
+    let scen = parse_scen_file(Path::new("./tests/arena.map.scen")).unwrap();
+    assert_eq!(map.map_type(), MapType::Octile);
+    let h = heuristics::for_map_type(map.map_type());
+    for record in &scen {
+        let (a, b) = (record.start_pos, record.goal_pos);
+        let optimal = record.optimal_length + 0.0001;
+        assert!(h(a, b) <= optimal);
+        assert!(heuristics::chebyshev(a, b) <= heuristics::euclidean(a, b));
+        assert!(heuristics::euclidean(a, b) <= heuristics::octile(a, b));
+        assert!(heuristics::octile(a, b) <= heuristics::manhattan(a, b));
+    }
+    let record = &scen[scen.len() - 1];
+    let result = astar_with_heuristic(&map, record.start_pos, record.goal_pos, h).unwrap();
+    assert!((result.cost - record.optimal_length).abs() < 0.0001);
+
+    // On empty maps the heuristics are exact.
+    let map = MovingAiMap::new(String::from("four-connected"), 8, 8, vec!['.'; 64]).unwrap();
+    let h = heuristics::for_map_type(map.map_type());
+    assert_eq!(astar(&map, (1, 2), (6, 7)).unwrap().cost, h((1, 2), (6, 7)));
+    let map = MovingAiMap::new(String::from("octile"), 8, 8, vec!['.'; 64]).unwrap();
+    let h = heuristics::for_map_type(map.map_type());
+    assert_eq!(astar(&map, (1, 2), (6, 4)).unwrap().cost, h((1, 2), (6, 4)));
+}
+
+#[test]
+fn hex_and_sixteen_connected_maps() {
+    let map =
+        parse_map("type hex\nheight 5\nwidth 5\nmap\n.....\n.....\n.....\n.....\n.....\n").unwrap();
+    assert_eq!(map.map_type(), MapType::Hex);
+    assert!(write_map(&map).starts_with("type hex\n"));
+    let neighbors = map.neighbors((2, 2));
+    assert_eq!(neighbors.len(), 6);
+    assert!(neighbors.contains(&(3, 1)) && neighbors.contains(&(1, 3)));
+    assert!(!neighbors.contains(&(3, 3)) && !neighbors.contains(&(1, 1)));
+    assert!(map.knight_neighbors((2, 2)).is_empty());
+    for goal in map.coords() {
+        let result = astar(&map, (0, 4), goal).unwrap();
+        assert_eq!(result.cost, heuristics::hex((0, 4), goal));
+        assert_eq!(map.validate_path(&result.path), Ok(result.cost));
+    }
+
+    let tiles = vec!['.'; 25];
+    let map = MovingAiMap::new(String::from("sixteen-connected"), 5, 5, tiles).unwrap();
+    assert_eq!(map.map_type(), MapType::SixteenConnected);
+    assert_eq!(map.neighbors((2, 2)).len(), 8);
+    let knights = map.knight_neighbors((2, 2));
+    assert_eq!(knights.len(), 8);
+    assert!(knights.contains(&(4, 3)) && knights.contains(&(1, 0)));
+    assert!(map.knight_neighbors((0, 0)).contains(&(1, 2)));
+    assert_eq!(map.step_cost((2, 2), (4, 3)), 5f64.sqrt());
+    let distances = dijkstra(&map, (0, 0), false);
+    for goal in map.coords() {
+        let expected = heuristics::sixteen_connected((0, 0), goal);
+        assert!((distances.distance(goal) - expected).abs() < 0.0001);
+    }
+
+    // A knight move crosses the two tiles between its ends.
+    let mut tiles = vec!['.'; 25];
+    tiles[5 + 1] = 'T';
+    let map = MovingAiMap::new(String::from("sixteen-connected"), 5, 5, tiles).unwrap();
+    assert!(!map.is_traversable_from((0, 0), (2, 1)));
+    assert!(!map.is_traversable_from((0, 0), (1, 2)));
+    assert!(map.is_traversable_from((0, 4), (2, 3)));
+    let map = map.with_diagonal_policy(DiagonalPolicy::OneSideFree);
+    assert!(map.is_traversable_from((0, 0), (2, 1)));
+}
+
+#[test]
+fn strongly_connected_components() {
+    let map = parse_map_file(Path::new("./tests/maze512-32-9.map")).unwrap();
+    let scen = parse_scen_file(Path::new("./tests/maze512-32-9.map.scen")).unwrap();
+    let components = label_components(&map);
+    assert_eq!(components.sizes().iter().sum::<usize>(), map.free_states());
+    for record in &scen {
+        assert!(components.same_component(record.start_pos, record.goal_pos));
+    }
+
+    // Water and swamp make some moves one-way.
+    let rows = ["..T.W", "S.TWW", "TTT..", "W.S.S", "WW..T"];
+    let tiles: Vec<char> = rows.iter().flat_map(|row| row.chars()).collect();
+    let map = MovingAiMap::new(String::from("octile"), 5, 5, tiles).unwrap();
+    let components = label_components(&map);
+    assert_eq!(components.sizes().iter().sum::<usize>(), map.free_states());
+    for from in map.coords() {
+        let distances = dijkstra(&map, from, false);
+        for to in map.coords() {
+            let traversable = map.is_traversable(from) && map.is_traversable(to);
+            let reachable = traversable && distances.distance(to).is_finite();
+            assert_eq!(
+                components.can_reach(from, to),
+                reachable,
+                "{:?} -> {:?}",
+                from,
+                to
+            );
+            let back = traversable && dijkstra(&map, to, false).distance(from).is_finite();
+            assert_eq!(components.same_component(from, to), reachable && back);
+        }
+    }
+}
+
+#[test]
+fn generate_scenarios() {
+    let map = parse_map_file(Path::new("./tests/arena.map")).unwrap();
+    let options = GeneratorOptions {
+        seed: 7,
+        queries_per_bucket: 5,
+        max_starts: 300,
+        max_stale_starts: 300,
+    };
+    let scen = generate_scenario(&map, "arena.map", &options);
+    assert!(!scen.is_empty());
+    assert_eq!(scen.len() % 5, 0);
+    let components = label_components(&map);
+    for (i, record) in scen.iter().enumerate() {
+        assert_eq!(record.bucket as usize, i / 5);
+        assert_eq!(record.map_file, "arena.map");
+        assert_eq!((record.map_width, record.map_height), (49, 49));
+        assert!(components.same_component(record.start_pos, record.goal_pos));
+        let result = astar(&map, record.start_pos, record.goal_pos).unwrap();
+        assert!((result.cost - record.optimal_length).abs() < 0.0001);
+        assert_eq!(record.bucket, (record.optimal_length / 4.0).floor() as u32);
+    }
+
+    assert_eq!(generate_scenario(&map, "arena.map", &options), scen);
+    let other = GeneratorOptions { seed: 8, ..options };
+    assert_ne!(generate_scenario(&map, "arena.map", &other), scen);
+    // Giving up early keeps a prefix of the buckets.
+    let impatient = GeneratorOptions {
+        max_stale_starts: 1,
+        ..options
+    };
+    let prefix = generate_scenario(&map, "arena.map", &impatient);
+    assert!(!prefix.is_empty() && prefix.len() < scen.len());
+    assert_eq!(prefix[..], scen[..prefix.len()]);
+
+    let written = parse_scen(&write_scen(&scen, SCEN_PRECISION)).unwrap();
+    assert_eq!(written.len(), scen.len());
+    for (a, b) in written.iter().zip(&scen) {
+        assert_eq!(
+            (a.start_pos, a.goal_pos, a.bucket),
+            (b.start_pos, b.goal_pos, b.bucket)
+        );
+        assert!((a.optimal_length - b.optimal_length).abs() < 1e-7);
+    }
+
+    let blocked = MovingAiMap::new(String::from("octile"), 2, 2, vec!['T'; 4]).unwrap();
+    assert!(generate_scenario(&blocked, "blocked.map", &options).is_empty());
+}
+
+#[test]
+fn verify_scenarios() {
+    let map = parse_map_file(Path::new("./tests/arena.map")).unwrap();
+    let scen = parse_scen_file(Path::new("./tests/arena.map.scen")).unwrap();
+    assert!(verify_scenario(&map, &scen, 0.0001).is_empty());
+    let generated = generate_scenario(&map, "arena.map", &GeneratorOptions::default());
+    assert!(verify_scenario(&map, &generated, 0.0001).is_empty());
+
+    let mut broken = scen[..4].to_vec();
+    broken[0].map_width = 50;
+    broken[1].goal_pos = (60, 1);
+    broken[2].start_pos = (0, 0);
+    broken[3].optimal_length += 0.5;
+    let issues = verify_scenario(&map, &broken, 0.0001);
+    assert_eq!(issues.len(), 4);
+    assert_eq!(
+        issues[0],
+        ScenarioIssue::DimensionMismatch {
+            index: 0,
+            record: (50, 49),
+            map: (49, 49)
+        }
+    );
+    assert_eq!(
+        issues[1],
+        ScenarioIssue::OutOfBounds {
+            index: 1,
+            endpoint: Endpoint::Goal,
+            coords: (60, 1)
+        }
+    );
+    assert_eq!(
+        issues[2],
+        ScenarioIssue::Blocked {
+            index: 2,
+            endpoint: Endpoint::Start,
+            coords: (0, 0)
+        }
+    );
+    assert!(matches!(
+        issues[3],
+        ScenarioIssue::LengthMismatch { index: 3, .. }
+    ));
+    assert_eq!(
+        issues[2].to_string(),
+        "record 2: start (0, 0) cannot be traversed"
+    );
+
+    // Water can be left but not entered.
+    let map = MovingAiMap::new(String::from("octile"), 1, 3, vec!['.', '.', 'W']).unwrap();
+    let mut record = scen[0].clone();
+    (record.map_width, record.map_height) = (3, 1);
+    (record.start_pos, record.goal_pos) = ((0, 0), (2, 0));
+    let issues = verify_scenario(&map, &[record], 0.0001);
+    assert_eq!(issues, vec![ScenarioIssue::Unreachable { index: 0 }]);
+}
+
+#[test]
+fn load_datasets() {
+    // The tests directory is flat: `maps/dao/arena.map` falls back on `arena.map`.
+    let dataset = Dataset::open("./tests").unwrap();
+    assert_eq!(dataset.scenarios().len(), 3);
+    assert_eq!(
+        dataset.resolve_map(Path::new("./tests/arena.map.scen"), "maps/dao/arena.map"),
+        Some(Path::new("./tests/arena.map").to_path_buf())
+    );
+    let entries: Vec<_> = dataset.iter().collect();
+    assert_eq!(entries.len(), 3);
+    let (map, records) = entries[0].as_ref().unwrap();
+    assert_eq!((map.width(), map.height()), (49, 49));
+    assert_eq!(
+        records,
+        &parse_scen_file(Path::new("./tests/arena.map.scen")).unwrap()
+    );
+    match &entries[1] {
+        Err(DatasetError::MapNotFound { map_file, .. }) => {
+            assert_eq!(map_file, "maps/dao/arena2.map")
+        }
+        other => panic!("unexpected {:?}", other),
+    }
+    assert_eq!(entries[2].as_ref().unwrap().0.width(), 512);
+
+    // The layout of the MovingAI downloads.
+    let root = std::env::temp_dir().join(format!("movingai-dataset-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&root);
+    fs::create_dir_all(root.join("maps/dao")).unwrap();
+    fs::create_dir_all(root.join("maps/other")).unwrap();
+    fs::create_dir_all(root.join("scen/dao")).unwrap();
+    fs::copy("./tests/arena.map", root.join("maps/dao/arena.map")).unwrap();
+    fs::write(root.join("maps/other/arena.map"), "not a map").unwrap();
+    fs::copy(
+        "./tests/arena.map.scen",
+        root.join("scen/dao/arena.map.scen"),
+    )
+    .unwrap();
+    fs::copy(
+        "./tests/arena.map.scen",
+        root.join("scen/dao/copy.map.scen"),
+    )
+    .unwrap();
+
+    let dataset = Dataset::open(&root).unwrap();
+    assert_eq!(dataset.maps().len(), 2);
+    let scen = root.join("scen/dao/arena.map.scen");
+    assert_eq!(
+        dataset.resolve_map(&scen, "maps/dao/arena.map"),
+        Some(root.join("maps/dao/arena.map"))
+    );
+    // The basename fallback prefers the longest matching suffix.
+    assert_eq!(
+        dataset.resolve_map(&scen, "benchmarks/dao/arena.map"),
+        Some(root.join("maps/dao/arena.map"))
+    );
+    assert_eq!(dataset.resolve_map(&scen, "arena2.map"), None);
+    // Records cannot point outside of the dataset.
+    assert_eq!(dataset.resolve_map(&scen, "../../maps/dao/arena.map"), None);
+    let absolute = root.join("maps/dao/arena.map");
+    assert_eq!(dataset.resolve_map(&scen, absolute.to_str().unwrap()), None);
+
+    let entries: Vec<_> = dataset.iter().map(Result::unwrap).collect();
+    assert_eq!(entries.len(), 2);
+    assert!(Arc::ptr_eq(&entries[0].0, &entries[1].0));
+    assert_eq!(entries[0].1, parse_scen_file(&scen).unwrap());
+
+    let error = dataset
+        .load_map(&root.join("maps/other/arena.map"))
+        .unwrap_err();
+    assert!(matches!(error, DatasetError::Parse { .. }));
+
+    // A scenario outside of the root does not look for maps above the root.
+    let outside = root.join("outside");
+    fs::create_dir_all(outside.join("maps/dao")).unwrap();
+    fs::create_dir_all(outside.join("data/maps/dao")).unwrap();
+    fs::create_dir_all(outside.join("scen")).unwrap();
+    fs::write(outside.join("maps/dao/arena.map"), "not a map").unwrap();
+    fs::copy("./tests/arena.map", outside.join("data/maps/dao/arena.map")).unwrap();
+    let dataset = Dataset::open(outside.join("data")).unwrap();
+    assert_eq!(
+        dataset.resolve_map(&outside.join("scen/arena.map.scen"), "maps/dao/arena.map"),
+        Some(outside.join("data/maps/dao/arena.map"))
+    );
+
+    // Symbolic links to directories are not followed.
+    #[cfg(unix)]
+    {
+        std::os::unix::fs::symlink(&root, root.join("scen/dao/loop")).unwrap();
+        let dataset = Dataset::open(&root).unwrap();
+        assert_eq!(dataset.scenarios().len(), 2);
+    }
+    fs::remove_dir_all(&root).unwrap();
+}
+
+#[test]
+fn render_maps() {
+    let map = MovingAiMap::new(String::from("octile"), 3, 4, vec!['.'; 12]).unwrap();
+    assert_eq!(render(&map, &Overlay::default()), "....\n....\n....\n");
+
+    let path = [(0, 2), (1, 1), (2, 1), (3, 0)];
+    assert_eq!(render(&map, &Overlay::path(&path)), "...g\n.>/.\ns...\n");
+    let reversed: Vec<_> = path.iter().rev().copied().collect();
+    assert_eq!(
+        render(&map, &Overlay::path(&reversed)),
+        "...s\n./<.\ng...\n"
+    );
+
+    let overlay = Overlay {
+        path: &path,
+        path_style: PathStyle::Stars,
+        expanded: &[(0, 2), (0, 1), (9, 9)],
+        generated: &[(0, 1), (0, 0), (1, 2)],
+        ..Overlay::default()
+    };
+    assert_eq!(render(&map, &overlay), "+..*\nx**.\n*+..\n");
+
+    // Windows are cropped to the map.
+    let window = Window::around((3, 2), 1);
+    assert_eq!(
+        window,
+        Window {
+            origin: (2, 1),
+            width: 3,
+            height: 3
+        }
+    );
+    assert_eq!(render_window(&map, window, &overlay), "*.\n..\n");
+    let everything = Window::around((0, 0), usize::MAX);
+    assert_eq!(
+        render_window(&map, everything, &overlay),
+        render(&map, &overlay)
+    );
+    let outside = Window {
+        origin: (4, 0),
+        width: 2,
+        height: 2,
+    };
+    assert_eq!(render_window(&map, outside, &overlay), "");
+
+    // Arena with the path of a scenario.
+    let map = parse_map_file(Path::new("./tests/arena.map")).unwrap();
+    let scen = parse_scen_file(Path::new("./tests/arena.map.scen")).unwrap();
+    let record = &scen[20];
+    let result = astar(&map, record.start_pos, record.goal_pos).unwrap();
+    let text = render(&map, &Overlay::path(&result.path));
+    let rows: Vec<&str> = text.lines().collect();
+    assert_eq!(rows.len(), map.height());
+    assert!(rows.iter().all(|row| row.len() == map.width()));
+    let at = |(x, y): (usize, usize)| rows[y].as_bytes()[x] as char;
+    assert_eq!(at(record.start_pos), 's');
+    assert_eq!(at(record.goal_pos), 'g');
+    for step in result.path[1..result.path.len() - 1].iter() {
+        assert!("><^v/\\".contains(at(*step)));
+    }
+}
+
+#[test]
+fn export_images() {
+    let map = MovingAiMap::new(String::from("octile"), 2, 2, vec!['.', 'W', '@', '?']).unwrap();
+    let options = ImageOptions {
+        palette: Palette::default().with('?', [1, 2, 3]),
+        scale: 2,
+        ..ImageOptions::default()
+    };
+    let mut image = Vec::new();
+    write_ppm(&map, &Overlay::default(), &options, &mut image).unwrap();
+    let header = b"P6\n4 4\n255\n";
+    assert_eq!(&image[..header.len()], header);
+    let pixels = &image[header.len()..];
+    assert_eq!(pixels.len(), 4 * 4 * 3);
+    let pixel = |x: usize, y: usize| &pixels[(y * 4 + x) * 3..(y * 4 + x) * 3 + 3];
+    assert_eq!(pixel(0, 0), [255, 255, 255]);
+    assert_eq!(pixel(1, 1), [255, 255, 255]);
+    assert_eq!(pixel(2, 1), [0, 0, 255]);
+    assert_eq!(pixel(1, 2), [0, 0, 0]);
+    assert_eq!(pixel(3, 3), [1, 2, 3]);
+
+    // Overlays.
+    let path = [(0, 0), (1, 0)];
+    let overlay = Overlay {
+        expanded: &[(0, 1)],
+        generated: &[(0, 1), (1, 1)],
+        ..Overlay::path(&path)
+    };
+    let options = ImageOptions::default();
+    let mut image = Vec::new();
+    write_ppm(&map, &overlay, &options, &mut image).unwrap();
+    assert_eq!(
+        &image[b"P6\n2 2\n255\n".len()..],
+        [
+            options.start,
+            options.goal,
+            options.expanded,
+            options.generated
+        ]
+        .concat()
+    );
+
+    let mut image = Vec::new();
+    write_pgm(&map, &Overlay::default(), &options, &mut image).unwrap();
+    assert_eq!(image, b"P5\n2 2\n255\n\xff\x1d\x00\x80");
+
+    let options = ImageOptions {
+        scale: 0,
+        ..ImageOptions::default()
+    };
+    let error = write_ppm(&map, &Overlay::default(), &options, Vec::new()).unwrap_err();
+    assert_eq!(error.kind(), std::io::ErrorKind::InvalidInput);
+    let options = ImageOptions {
+        scale: usize::MAX / 2 + 1,
+        ..ImageOptions::default()
+    };
+    let mut image = Vec::new();
+    let error = write_pgm(&map, &Overlay::default(), &options, &mut image).unwrap_err();
+    assert_eq!(error.kind(), std::io::ErrorKind::InvalidInput);
+    assert!(image.is_empty());
+
+    // Arena with the path of a scenario.
+    let map = parse_map_file(Path::new("./tests/arena.map")).unwrap();
+    let result = astar(&map, (1, 11), (1, 12)).unwrap();
+    let file = std::env::temp_dir().join(format!("movingai-arena-{}.ppm", std::process::id()));
+    let options = ImageOptions {
+        scale: 3,
+        ..ImageOptions::default()
+    };
+    write_ppm_file(&file, &map, &Overlay::path(&result.path), &options).unwrap();
+    let image = fs::read(&file).unwrap();
+    fs::remove_file(&file).unwrap();
+    let header = b"P6\n147 147\n255\n";
+    assert_eq!(&image[..header.len()], header);
+    assert_eq!(image.len(), header.len() + 147 * 147 * 3);
+}